@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use asar::HashAlgorithm;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+
+const BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+fn hashing(c: &mut Criterion) {
+	let mut group = c.benchmark_group("file integrity");
+	for size in [4 * 1024, 256 * 1024, BLOCK_SIZE, 3 * BLOCK_SIZE] {
+		let data = vec![0x42_u8; size];
+		group.throughput(Throughput::Bytes(size as u64));
+		group.bench_with_input(
+			BenchmarkId::new("hash + hash_blocks", size),
+			&data,
+			|b, data| {
+				b.iter(|| {
+					(
+						HashAlgorithm::Sha256.hash(black_box(data)),
+						HashAlgorithm::Sha256.hash_blocks(BLOCK_SIZE, black_box(data)),
+					)
+				})
+			},
+		);
+		group.bench_with_input(
+			BenchmarkId::new("hash_with_blocks", size),
+			&data,
+			|b, data| {
+				b.iter(|| HashAlgorithm::Sha256.hash_with_blocks(BLOCK_SIZE, black_box(data)))
+			},
+		);
+	}
+	group.finish();
+}
+
+criterion_group!(benches, hashing);
+criterion_main!(benches);
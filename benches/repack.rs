@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use asar::{AsarReader, AsarWriter};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+
+const FILE_SIZE: usize = 6 * 1024 * 1024;
+
+fn archive(files: usize) -> Vec<u8> {
+	let mut writer = AsarWriter::new();
+	for idx in 0..files {
+		let data = vec![idx as u8; FILE_SIZE];
+		writer
+			.write_file(format!("file {idx}.bin"), &data, false)
+			.expect("failed to write file");
+	}
+	let mut archive = Vec::new();
+	writer.finalize(&mut archive).expect("failed to finalize");
+	archive
+}
+
+fn repack(c: &mut Criterion) {
+	let mut group = c.benchmark_group("repack");
+	group.sample_size(10);
+	for files in [1, 16] {
+		let archive = archive(files);
+		let reader = AsarReader::new(&archive, None).expect("failed to read archive");
+		group.throughput(Throughput::Bytes((files * FILE_SIZE) as u64));
+		group.bench_with_input(
+			BenchmarkId::new("add_from_reader", files),
+			&reader,
+			|b, reader| {
+				b.iter(|| {
+					let mut writer = AsarWriter::new();
+					writer
+						.add_from_reader(black_box(reader))
+						.expect("failed to add files");
+					writer
+				})
+			},
+		);
+	}
+	group.finish();
+}
+
+criterion_group!(benches, repack);
+criterion_main!(benches);
@@ -2,5 +2,8 @@
 pub mod args;
 pub mod extract;
 pub mod extract_file;
+pub mod header;
 pub mod list;
 pub mod pack;
+pub mod pack_file;
+pub mod verify;
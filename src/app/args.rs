@@ -1,5 +1,9 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
-use clap::{Args, Parser, Subcommand};
+use asar::HashAlgorithm;
+use clap::{
+	builder::{PossibleValuesParser, TypedValueParser},
+	Args, Parser, Subcommand, ValueEnum,
+};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -15,33 +19,102 @@ pub struct AppArgs {
 #[derive(Subcommand)]
 pub enum AppSubcommand {
 	Pack(PackArgs),
+	PackFile(PackFileArgs),
 	List(ListArgs),
 	Extract(ExtractArgs),
 	ExtractFile(ExtractFileArgs),
+	Header(HeaderArgs),
+	Verify(VerifyArgs),
 }
 
 /// Create asar archive
 #[derive(Args)]
+// With `--from-json`, the only positional argument is the output.
+#[clap(allow_missing_positional = true)]
 pub struct PackArgs {
 	/// Path to a text file for ordering contents
-	#[clap(long)]
+	#[clap(long, conflicts_with = "from_json")]
 	pub ordering: Option<PathBuf>,
+	/// The order to write files not listed in the ordering file in
+	#[clap(long, value_enum, default_value_t = SortArg::Name, conflicts_with = "from_json")]
+	pub sort: SortArg,
 	/// Do not pack files matching glob <expression>
-	#[clap(long)]
+	#[clap(long, conflicts_with = "from_json")]
 	pub unpack: Option<String>,
 	/// Do not pack dirs matching glob <expression> or starting with literal
 	/// <expression>
-	#[clap(long)]
+	#[clap(long, conflicts_with = "from_json")]
 	pub unpack_dir: Option<String>,
-	/// Exclude hidden files
-	#[clap(long)]
+	/// Mark files matching glob <expression> as executable
+	#[clap(long, conflicts_with = "from_json")]
+	pub executable: Option<String>,
+	/// Exclude hidden files, and files inside hidden directories
+	#[clap(long, conflicts_with = "from_json")]
 	pub exclude_hidden: bool,
+	/// Record the modification time of each file, so that it's restored on
+	/// extraction
+	#[clap(long, conflicts_with = "from_json")]
+	pub record_mtimes: bool,
+	/// Follow symbolic links to directories, packing their contents. Symbolic
+	/// links to files are always packed as links
+	#[clap(long, conflicts_with = "from_json")]
+	pub follow_symlinks: bool,
+	/// Read and hash files on <N> threads, or on one per CPU if <N> is 0
+	#[clap(long, short, value_name = "N", conflicts_with = "from_json")]
+	pub jobs: Option<usize>,
+	/// Compress each file with gzip. The archive can then only be read by
+	/// this tool, not by Electron
+	#[cfg(feature = "compression")]
+	#[clap(long)]
+	pub compress: bool,
+	/// Print the files that would be packed, with their sizes, and the size
+	/// of the archive, without reading the files or writing the archive. The
+	/// size is of the archive without any compression
+	#[clap(long)]
+	pub dry_run: bool,
+	/// Pack the files described by a JSON object in <file> ("-" for stdin),
+	/// instead of a directory. Each key is a path, and each value is either
+	/// the file's contents in base64, or {"link": "<target>"}. Only the output
+	/// archive is given after this
+	#[clap(long, value_name = "file")]
+	pub from_json: Option<PathBuf>,
 	/// The directory to pack
+	#[clap(
+		value_parser,
+		required_unless_present = "from_json",
+		conflicts_with = "from_json"
+	)]
+	pub dir: Option<PathBuf>,
+	/// The output asar archive
+	#[clap(value_parser)]
+	pub output: PathBuf,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum SortArg {
+	/// By path
+	Name,
+	/// By size, smallest first
+	Size,
+	/// By extension, then by path
+	Extension,
+}
+
+/// Create an asar archive containing a single file
+#[derive(Args)]
+pub struct PackFileArgs {
+	/// The file to pack
 	#[clap(value_parser)]
-	pub dir: PathBuf,
+	pub file: PathBuf,
 	/// The output asar archive
 	#[clap(value_parser)]
 	pub output: PathBuf,
+	/// The path of the file in the archive, rather than its file name
+	#[clap(long, value_name = "path")]
+	pub name: Option<PathBuf>,
+	/// Mark the file as executable, regardless of its permissions
+	#[clap(long)]
+	pub executable: bool,
 }
 
 /// List files of asar archive
@@ -50,6 +123,12 @@ pub struct ListArgs {
 	/// The asar archive to list
 	#[clap(value_parser)]
 	pub archive: PathBuf,
+	/// Mark unpacked files with [unpacked]
+	#[clap(long)]
+	pub list_unpacked: bool,
+	/// Print a summary of the archive's contents after listing them
+	#[clap(long)]
+	pub total: bool,
 }
 
 /// Extract an asar archive
@@ -58,9 +137,43 @@ pub struct ExtractArgs {
 	/// Archive to extract
 	#[clap(value_parser)]
 	pub archive: PathBuf,
-	/// The directory to extract to
+	/// The directory to extract to, or the file to write with --as
 	#[clap(value_parser)]
 	pub destination: PathBuf,
+	/// Write the contents to a single archive of this format, instead of
+	/// loose files
+	#[clap(long = "as", value_enum, value_name = "format")]
+	pub format: Option<ExtractFormat>,
+	/// Strip <path> from the start of each entry, skipping entries that don't
+	/// start with it
+	#[clap(long, value_name = "path")]
+	pub strip_prefix: Option<PathBuf>,
+	/// How to handle paths that can't be created on Windows, such as `aux`
+	#[clap(long, value_enum, default_value_t = WindowsNamesArg::Allow)]
+	pub windows_names: WindowsNamesArg,
+	/// Create symbolic links which point outside of the destination, such as
+	/// to absolute system paths. Only use this for trusted archives
+	#[clap(long)]
+	pub unsafe_links: bool,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum ExtractFormat {
+	/// A tar archive, keeping executable bits, modification times, and
+	/// symbolic links
+	Tar,
+	/// A zip archive, keeping executable bits and symbolic links
+	Zip,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum WindowsNamesArg {
+	/// Extract paths as-is
+	Allow,
+	/// Fail if a path can't be created on Windows
+	Reject,
+	/// Rename paths so they can be created on Windows
+	Sanitize,
 }
 
 /// Extract one file from an asar archive
@@ -72,4 +185,46 @@ pub struct ExtractFileArgs {
 	/// The file to extract from the archive
 	#[clap(value_parser)]
 	pub filename: PathBuf,
+	/// Recreate the file's parent directories from the archive, rather than
+	/// only using its file name
+	#[clap(long)]
+	pub parents: bool,
+	/// The directory to extract the file to
+	#[clap(long, short, value_name = "dir", default_value = ".")]
+	pub output: PathBuf,
+}
+
+/// Print the header of an asar archive as JSON
+#[derive(Args)]
+pub struct HeaderArgs {
+	/// The asar archive to read the header of
+	#[clap(value_parser)]
+	pub archive: PathBuf,
+	/// Pretty-print the header, rather than printing it exactly as it's
+	/// stored
+	#[clap(long)]
+	pub pretty: bool,
+}
+
+/// Check the contents of an asar archive against its integrity details
+#[derive(Args)]
+pub struct VerifyArgs {
+	/// The asar archive to verify
+	#[clap(value_parser)]
+	pub archive: PathBuf,
+	/// Also check the archive against a JSON manifest of the files it should
+	/// contain and their hashes, and the symbolic links it should contain and
+	/// their targets, reporting missing, extra, and mismatched entries. Files
+	/// without a hash are reported, but not counted as problems
+	#[clap(long, alias = "compare-with", value_name = "file")]
+	pub manifest: Option<PathBuf>,
+	/// The hashing algorithm used for files in the manifest which don't list
+	/// one
+	#[clap(
+		long,
+		default_value = "SHA256",
+		value_parser = PossibleValuesParser::new(HashAlgorithm::all().iter().map(HashAlgorithm::name))
+			.map(|name| name.parse::<HashAlgorithm>().expect("name should be valid"))
+	)]
+	pub algorithm: HashAlgorithm,
 }
@@ -1,8 +1,17 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
-use super::args::ExtractArgs;
-use asar::AsarReader;
+use super::args::{ExtractArgs, ExtractFormat, WindowsNamesArg};
+use asar::{
+	extract::{ExtractOptions, WindowsNames},
+	AsarReader,
+};
 use color_eyre::{eyre::WrapErr, Result};
-use std::fs;
+use std::{
+	fs::{self, File},
+	io::{BufWriter, Write},
+	path::{Component, Path, PathBuf},
+	time::UNIX_EPOCH,
+};
+use zip::{write::SimpleFileOptions, ZipWriter};
 
 pub fn extract(args: ExtractArgs, read_unpacked: bool) -> Result<()> {
 	let file = fs::read(&args.archive)
@@ -13,43 +22,174 @@ pub fn extract(args: ExtractArgs, read_unpacked: bool) -> Result<()> {
 		None
 	};
 	let reader = AsarReader::new(&file, asar_path).wrap_err("failed to read archive")?;
-	for path in reader.directories().keys() {
-		let out_path = args.destination.join(path);
-		if !out_path.starts_with(&args.destination) {
-			panic!("asar archive attempted to escape destination");
+	let windows_names = match args.windows_names {
+		WindowsNamesArg::Allow => WindowsNames::Allow,
+		WindowsNamesArg::Reject => WindowsNames::Reject,
+		WindowsNamesArg::Sanitize => WindowsNames::Sanitize,
+	};
+	let options = ExtractOptions {
+		strip_prefix: args.strip_prefix,
+		windows_names,
+		unsafe_links: args.unsafe_links,
+	};
+	if options.unsafe_links {
+		let safe = ExtractOptions {
+			unsafe_links: false,
+			..options.clone()
+		};
+		for (path, link) in reader.symlinks() {
+			if safe.link_target(Path::new(ROOT), link).is_err() {
+				eprintln!(
+					"warning: symbolic link {} points outside of the destination, to {}",
+					path.display(),
+					link.display()
+				);
+			}
 		}
-		if !out_path.exists() {
-			fs::create_dir_all(&out_path)
-				.wrap_err_with(|| format!("failed to write directory {}", out_path.display()))?;
+	}
+	match args.format {
+		None => reader
+			.extract_to(&args.destination, &options)
+			.wrap_err_with(|| format!("failed to extract to {}", args.destination.display()))?,
+		Some(format) => {
+			let out = File::create(&args.destination)
+				.wrap_err_with(|| format!("failed to create {}", args.destination.display()))?;
+			let out = BufWriter::new(out);
+			match format {
+				ExtractFormat::Tar => write_tar(&reader, &options, out),
+				ExtractFormat::Zip => write_zip(&reader, &options, out),
+			}
+			.wrap_err_with(|| format!("failed to write {}", args.destination.display()))?;
 		}
 	}
-	for (path, file) in reader.files() {
-		let out_path = args.destination.join(path);
-		if !out_path.starts_with(&args.destination) {
-			panic!("asar archive attempted to escape destination");
+
+	Ok(())
+}
+
+/// A stand-in destination for working out where entries go, and whether they
+/// escape it, as every path would be inside an empty destination.
+const ROOT: &str = "root";
+
+/// Makes a path returned by [`ExtractOptions::out_path`] for [`ROOT`] relative
+/// to it. Link targets outside of it are kept as they are.
+fn strip_root(path: Option<PathBuf>) -> Option<PathBuf> {
+	path.map(|path| match path.strip_prefix(ROOT) {
+		Ok(stripped) => stripped.to_path_buf(),
+		Err(_) => path,
+	})
+}
+
+/// The entries of the archive, with the paths they should be written to
+/// within a tar or zip, with symbolic links made relative to the directory
+/// containing them.
+struct Entries<'r, 'a> {
+	directories: Vec<PathBuf>,
+	files: Vec<(PathBuf, &'r asar::reader::AsarFile<'a>)>,
+	symlinks: Vec<(PathBuf, PathBuf)>,
+}
+
+impl<'r, 'a> Entries<'r, 'a> {
+	fn new(reader: &'r AsarReader<'a>, options: &ExtractOptions) -> Result<Self> {
+		let out_path = |path: &Path| options.out_path(Path::new(ROOT), path).map(strip_root);
+		let mut entries = Self {
+			directories: Vec::new(),
+			files: Vec::new(),
+			symlinks: Vec::new(),
+		};
+		for path in reader.directories().keys() {
+			// The root of the archive isn't an entry of its own.
+			match out_path(path)? {
+				Some(path) if path.as_os_str().is_empty() => {}
+				Some(path) => entries.directories.push(path),
+				None => {}
+			}
 		}
-		fs::write(&out_path, file.data())
-			.wrap_err_with(|| format!("failed to write file {}", out_path.display()))?;
-	}
-	for (path, link) in reader.symlinks() {
-		let out_path = args.destination.join(path);
-		let out_link = args.destination.join(link);
-		if !out_path.starts_with(&args.destination) || !out_link.starts_with(&args.destination) {
-			panic!("asar archive attempted to escape destination");
+		for (path, file) in reader.files() {
+			if let Some(path) = out_path(path)? {
+				entries.files.push((path, file));
+			}
 		}
-		#[cfg(unix)]
-		{
-			std::os::unix::fs::symlink(out_link, &out_path).wrap_err_with(|| {
-				format!("failed to write symbolic link {}", out_path.display())
-			})?;
+		for (path, link) in reader.symlinks() {
+			let link = strip_root(options.link_target(Path::new(ROOT), link)?);
+			if let (Some(path), Some(link)) = (out_path(path)?, link) {
+				let link = relative_link(&path, &link);
+				entries.symlinks.push((path, link));
+			}
 		}
-		#[cfg(windows)]
-		{
-			std::os::windows::fs::symlink_file(out_link, &out_path).wrap_err_with(|| {
-				format!("failed to write symbolic link {}", out_path.display())
-			})?;
+		Ok(entries)
+	}
+}
+
+/// Links in an asar are relative to its root, but links in a tar or zip are
+/// relative to the directory containing them.
+fn relative_link(path: &Path, link: &Path) -> PathBuf {
+	let depth = path
+		.parent()
+		.map_or(0, |parent| parent.components().count());
+	let mut relative = (0..depth)
+		.map(|_| Component::ParentDir)
+		.collect::<PathBuf>();
+	relative.push(link);
+	relative
+}
+
+fn write_tar(reader: &AsarReader, options: &ExtractOptions, out: impl Write) -> Result<()> {
+	let entries = Entries::new(reader, options)?;
+	let mut tar = tar::Builder::new(out);
+	for path in &entries.directories {
+		let mut header = tar::Header::new_gnu();
+		header.set_entry_type(tar::EntryType::Directory);
+		header.set_mode(0o755);
+		header.set_size(0);
+		tar.append_data(&mut header, path, &[][..])?;
+	}
+	for (path, file) in &entries.files {
+		let mut header = tar::Header::new_gnu();
+		header.set_mode(if file.executable() { 0o755 } else { 0o644 });
+		header.set_size(file.data().len() as u64);
+		if let Some(mtime) = file.mtime() {
+			let mtime = mtime.duration_since(UNIX_EPOCH).unwrap_or_default();
+			header.set_mtime(mtime.as_secs());
 		}
+		tar.append_data(&mut header, path, file.data())?;
+	}
+	for (path, link) in &entries.symlinks {
+		let mut header = tar::Header::new_gnu();
+		header.set_entry_type(tar::EntryType::Symlink);
+		header.set_mode(0o777);
+		header.set_size(0);
+		tar.append_link(&mut header, path, link)?;
 	}
+	tar.into_inner()?.flush()?;
+	Ok(())
+}
 
+fn write_zip(
+	reader: &AsarReader,
+	options: &ExtractOptions,
+	out: impl Write + std::io::Seek,
+) -> Result<()> {
+	let entries = Entries::new(reader, options)?;
+	// Zip entries are always named with forward slashes.
+	let name = |path: &Path| {
+		path.components()
+			.map(|component| component.as_os_str().to_string_lossy())
+			.collect::<Vec<_>>()
+			.join("/")
+	};
+	let mut zip = ZipWriter::new(out);
+	for path in &entries.directories {
+		zip.add_directory(name(path), SimpleFileOptions::default())?;
+	}
+	for (path, file) in &entries.files {
+		let permissions = if file.executable() { 0o755 } else { 0o644 };
+		let options = SimpleFileOptions::default().unix_permissions(permissions);
+		zip.start_file(name(path), options)?;
+		zip.write_all(file.data())?;
+	}
+	for (path, link) in &entries.symlinks {
+		zip.add_symlink(name(path), name(link), SimpleFileOptions::default())?;
+	}
+	zip.finish()?.flush()?;
 	Ok(())
 }
@@ -1,11 +1,11 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 use super::args::ExtractFileArgs;
-use asar::AsarReader;
+use asar::{extract::ExtractOptions, reader::EntryKind, AsarReader};
 use color_eyre::{
 	eyre::{eyre, WrapErr},
 	Result,
 };
-use std::{ffi::OsStr, fs, path::Path};
+use std::{fs, path::Path};
 
 pub fn extract_file(args: ExtractFileArgs, read_unpacked: bool) -> Result<()> {
 	let file = fs::read(&args.archive)
@@ -21,18 +21,33 @@ pub fn extract_file(args: ExtractFileArgs, read_unpacked: bool) -> Result<()> {
 		.strip_prefix("/")
 		.map(Path::to_path_buf)
 		.unwrap_or_else(|_| args.filename.to_path_buf());
-	let file_name = path
-		.file_name()
-		.map(OsStr::to_string_lossy)
-		.ok_or_else(|| eyre!("failed to get file name for {}", path.display()))?
-		.into_owned();
-	let file = reader
-		.files()
-		.get(&path)
-		.ok_or_else(|| eyre!("failed to find file {}", path.display()))?;
+	let resolved = reader
+		.resolve(&path)
+		.wrap_err_with(|| format!("failed to resolve {}", path.display()))?;
+	let file = match reader.entry_kind(&resolved) {
+		Some(EntryKind::File) => &reader.files()[&resolved],
+		Some(EntryKind::Directory) => return Err(eyre!("{} is a directory", path.display())),
+		_ => return Err(eyre!("failed to find file {}", path.display())),
+	};
+
+	let out_path = if args.parents {
+		let out_path = ExtractOptions::default()
+			.out_path(&args.output, &path)?
+			.expect("paths are only skipped when stripping a prefix");
+		if let Some(parent) = out_path.parent() {
+			fs::create_dir_all(parent)
+				.wrap_err_with(|| format!("failed to create directory {}", parent.display()))?;
+		}
+		out_path
+	} else {
+		let file_name = path
+			.file_name()
+			.ok_or_else(|| eyre!("failed to get file name for {}", path.display()))?;
+		args.output.join(file_name)
+	};
 
-	fs::write(&file_name, file.data())
-		.wrap_err_with(|| format!("failed to write contents to {file_name}"))?;
+	fs::write(&out_path, file.data())
+		.wrap_err_with(|| format!("failed to write contents to {}", out_path.display()))?;
 
 	Ok(())
 }
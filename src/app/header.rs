@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use super::args::HeaderArgs;
+use asar::Header;
+use color_eyre::{eyre::WrapErr, Result};
+use std::io::{self, Write};
+
+pub fn header(args: HeaderArgs) -> Result<()> {
+	let file = std::fs::read(&args.archive)
+		.wrap_err_with(|| format!("failed to read archive {}", args.archive.display()))?;
+	let (json, _) = Header::read_raw(&mut &file[..]).wrap_err("failed to read header")?;
+	let mut stdout = io::stdout().lock();
+	if args.pretty {
+		let value: serde_json::Value =
+			serde_json::from_slice(&json).wrap_err("failed to parse header")?;
+		serde_json::to_writer_pretty(&mut stdout, &value).wrap_err("failed to print header")?;
+	} else {
+		stdout.write_all(&json).wrap_err("failed to print header")?;
+	}
+	writeln!(stdout).wrap_err("failed to print header")?;
+	Ok(())
+}
@@ -14,15 +14,32 @@ pub fn list(args: ListArgs, read_unpacked: bool) -> Result<()> {
 	};
 	let reader = AsarReader::new(&file, asar_path).wrap_err("failed to read archive")?;
 	let root = PathBuf::from(MAIN_SEPARATOR.to_string());
-	for path in reader.files().keys() {
+	for (path, file) in reader.files() {
 		let path = root.join(path);
-		println!("{}", path.display());
+		if args.list_unpacked && file.unpacked() {
+			println!("{} [unpacked]", path.display());
+		} else {
+			println!("{}", path.display());
+		}
 	}
 	for (path, link) in reader.symlinks() {
 		let path = root.join(path);
 		let link = root.join(link);
 		println!("{} -> {}", path.display(), link.display());
 	}
+	if args.total {
+		let counts = reader.header().count();
+		let size = reader
+			.manifest()
+			.files
+			.iter()
+			.map(|file| file.size)
+			.sum::<usize>();
+		println!(
+			"{} files, {} directories, {} symbolic links, {} bytes",
+			counts.files, counts.directories, counts.symlinks, size
+		);
+	}
 
 	Ok(())
 }
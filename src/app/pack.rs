@@ -1,97 +1,214 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
-use super::args::PackArgs;
-use asar::AsarWriter;
+use super::args::{PackArgs, SortArg};
+use asar::{
+	ordering::parse_ordering,
+	writer::{PackOptions, PackPlan, SortOrder},
+	AsarWriter,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use color_eyre::{
-	eyre::{eyre, WrapErr},
+	eyre::{bail, WrapErr},
 	Result,
 };
+use serde::Deserialize;
 use std::{
-	fs::{self, read_link, File},
-	io::BufWriter,
+	collections::BTreeMap,
+	fs,
+	io::{self, Read},
+	path::{Component, Path, PathBuf, MAIN_SEPARATOR},
 };
-use walkdir::WalkDir;
-use wax::{Glob, Pattern};
+
+/// An entry of the JSON object read by `--from-json`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+	/// The contents of a file, in base64.
+	File(String),
+	Link {
+		link: PathBuf,
+	},
+}
 
 pub fn pack(args: PackArgs) -> Result<()> {
+	#[cfg(feature = "compression")]
+	let mut asar = if args.compress {
+		eprintln!("warning: compressed archives can't be read by Electron");
+		AsarWriter::builder()
+			.compress(asar::header::CompressionMethod::Gzip)
+			.build()
+	} else {
+		AsarWriter::new()
+	};
+	#[cfg(not(feature = "compression"))]
 	let mut asar = AsarWriter::new();
-	let unpack = args
-		.unpack
-		.as_deref()
-		.map(Glob::new)
-		.transpose()
-		.map_err(|err| eyre!("failed to parse --unpack glob: {}", err))?;
-	let unpack_dir = args
-		.unpack_dir
-		.as_deref()
-		.map(Glob::new)
-		.transpose()
-		.map_err(|err| eyre!("failed to parse --unpack-dir glob: {}", err))?;
-	for entry in WalkDir::new(&args.dir) {
-		let entry = entry.wrap_err("failed to get directory entry")?;
-		let path = entry.path();
-		if !path.is_file() {
-			continue;
-		}
-		let stripped_path = path.strip_prefix(&args.dir).wrap_err_with(|| {
-			format!(
-				"'{}' is not a prefix of '{}'",
-				args.dir.display(),
-				path.display()
-			)
-		})?;
-		let file_name = path
-			.file_name()
-			.ok_or_else(|| eyre!("failed to get file name for {}", path.display()))?
-			.to_string_lossy();
-		if args.exclude_hidden && file_name.starts_with('.') {
-			continue;
-		}
-		if let (Some(parent), Some(unpack_dir_glob)) = (stripped_path.parent(), &unpack_dir) {
-			if unpack_dir_glob.is_match(parent) {
-				continue;
-			}
-		}
-		if let Some(unpack_glob) = &unpack {
-			if unpack_glob.is_match(stripped_path) {
-				continue;
-			}
-		}
 
-		if path.is_symlink() {
-			let link = read_link(path)
-				.wrap_err_with(|| format!("failed to read link of {}", path.display()))?;
-			let stripped_link = if link.is_absolute() {
-				link.strip_prefix(&args.dir).wrap_err_with(|| {
-					format!(
-						"'{}' is not a prefix of '{}'",
-						args.dir.display(),
-						link.display()
-					)
-				})?
-			} else {
-				&link
+	let output = args.output;
+	match (&args.from_json, args.dir) {
+		(Some(json), _) => pack_json(&mut asar, json)?,
+		(None, Some(dir)) => {
+			let ordering = match &args.ordering {
+				Some(path) => parse_ordering(path)
+					.wrap_err_with(|| format!("failed to read ordering file {}", path.display()))?,
+				None => Vec::new(),
 			};
-			asar.write_symlink(stripped_path, stripped_link)
-				.wrap_err_with(|| format!("failed to write {} to asar", path.display()))?;
-			continue;
+			let sort = match args.sort {
+				SortArg::Name => SortOrder::Name,
+				SortArg::Size => SortOrder::Size,
+				SortArg::Extension => SortOrder::Extension,
+			};
+			let options = PackOptions {
+				unpack: args.unpack,
+				unpack_dir: args.unpack_dir,
+				executable: args.executable,
+				exclude_hidden: args.exclude_hidden,
+				ordering,
+				sort,
+				record_mtimes: args.record_mtimes,
+				follow_symlinks: args.follow_symlinks,
+				jobs: args.jobs,
+			};
+			// A dry run only looks at the files' metadata, without reading them.
+			if args.dry_run {
+				let plan = asar
+					.plan_tree(&dir, &options)
+					.wrap_err_with(|| format!("failed to walk {}", dir.display()))?;
+				return dry_run(&plan, &output);
+			}
+			asar.write_tree(&dir, options)
+				.wrap_err_with(|| format!("failed to pack {}", dir.display()))?;
 		}
+		(None, None) => unreachable!("clap requires the directory without --from-json"),
+	}
 
-		let file = fs::read(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
-
-		asar.write_file(stripped_path, &file, is_executable::is_executable(path))
-			.wrap_err_with(|| format!("failed to write {} to asar", path.display()))?;
+	if args.dry_run {
+		let plan = asar.plan().wrap_err("failed to plan asar")?;
+		return dry_run(&plan, &output);
 	}
+	asar.finalize_to_path(&output)
+		.wrap_err_with(|| format!("failed to write asar to {}", output.display()))?;
 
-	let mut out = BufWriter::new(
-		File::create(&args.output)
-			.wrap_err_with(|| format!("failed to create {}", args.output.display()))?,
+	Ok(())
+}
+
+/// Prints what packing would write to `output`.
+fn dry_run(plan: &PackPlan, output: &Path) -> Result<()> {
+	let root = PathBuf::from(MAIN_SEPARATOR.to_string());
+	for (path, size) in &plan.files {
+		println!("{} ({size} bytes)", root.join(path).display());
+	}
+	for (path, link) in &plan.symlinks {
+		println!(
+			"{} -> {}",
+			root.join(path).display(),
+			root.join(link).display()
+		);
+	}
+	println!(
+		"would write {} files, {} symbolic links, {} bytes to {}",
+		plan.files.len(),
+		plan.symlinks.len(),
+		plan.size,
+		output.display()
 	);
-	asar.finalize(&mut out)
-		.wrap_err_with(|| format!("failed to write asar to {}", args.output.display()))?;
-	out.into_inner()
-		.wrap_err("failed to de-buf writer")?
-		.sync_all()
-		.wrap_err_with(|| format!("failed to sync {} to disk", args.output.display()))?;
+	Ok(())
+}
+
+fn pack_json(asar: &mut AsarWriter, json: &Path) -> Result<()> {
+	let json = if json == Path::new("-") {
+		let mut json = Vec::new();
+		io::stdin()
+			.read_to_end(&mut json)
+			.wrap_err("failed to read JSON from stdin")?;
+		json
+	} else {
+		fs::read(json).wrap_err_with(|| format!("failed to read {}", json.display()))?
+	};
+	let entries: BTreeMap<PathBuf, JsonEntry> =
+		serde_json::from_slice(&json).wrap_err("failed to parse JSON")?;
+	for (path, entry) in entries {
+		check_json_path(&path)?;
+		match entry {
+			JsonEntry::File(contents) => {
+				let contents = BASE64
+					.decode(contents)
+					.wrap_err_with(|| format!("invalid base64 for {}", path.display()))?;
+				asar.write_file(&path, contents, false)
+			}
+			JsonEntry::Link { link } => {
+				check_json_path(&link)?;
+				asar.write_symlink(&path, link)
+			}
+		}
+		.wrap_err_with(|| format!("failed to pack {}", path.display()))?;
+	}
+	Ok(())
+}
 
+/// Checks that a path from `--from-json`, or the target of a link, is a
+/// relative path to something inside the archive.
+fn check_json_path(path: &Path) -> Result<()> {
+	if path.as_os_str().is_empty()
+		|| path
+			.components()
+			.any(|component| !matches!(component, Component::Normal(_)))
+	{
+		bail!(
+			"invalid path {:?}: it must be a relative path without '.' or '..'",
+			path
+		);
+	}
 	Ok(())
 }
+
+#[cfg(test)]
+mod test {
+	use super::pack;
+	use crate::app::args::{AppArgs, AppSubcommand};
+	use clap::Parser;
+	use std::fs;
+
+	#[test]
+	fn from_json_invalid_paths() {
+		let dir = std::env::temp_dir().join("asar-rs-test-from-json-invalid-paths");
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).expect("failed to create directory");
+		let (json, output) = (dir.join("files.json"), dir.join("out.asar"));
+		let pack_args = || {
+			let args = AppArgs::try_parse_from([
+				"asar".as_ref(),
+				"pack".as_ref(),
+				"--from-json".as_ref(),
+				json.as_os_str(),
+				output.as_os_str(),
+			])
+			.expect("failed to parse arguments");
+			match args.subcommand {
+				AppSubcommand::Pack(args) => args,
+				_ => unreachable!("the subcommand should be pack"),
+			}
+		};
+		for files in [
+			r#"{".": "aGk="}"#,
+			r#"{"": "aGk="}"#,
+			r#"{"/a.txt": "aGk="}"#,
+			r#"{"./a.txt": "aGk="}"#,
+			r#"{"../a.txt": "aGk="}"#,
+			r#"{"a.txt": "aGk=", "link": {"link": "../a.txt"}}"#,
+			r#"{"a.txt": "aGk=", "link": {"link": "/a.txt"}}"#,
+			r#"{"a.txt": "aGk=", "link": {"link": ""}}"#,
+		] {
+			fs::write(&json, files).expect("failed to write JSON");
+			assert!(pack(pack_args()).is_err(), "{files} should be rejected");
+			assert!(!output.exists(), "{files} shouldn't be packed");
+		}
+
+		fs::write(
+			&json,
+			r#"{"dir/a.txt": "aGk=", "link": {"link": "dir/a.txt"}}"#,
+		)
+		.expect("failed to write JSON");
+		pack(pack_args()).expect("valid paths should be packed");
+		assert!(output.exists());
+		fs::remove_dir_all(&dir).expect("failed to clean up");
+	}
+}
@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use super::args::PackFileArgs;
+use asar::AsarWriter;
+use color_eyre::{
+	eyre::{bail, eyre, WrapErr},
+	Result,
+};
+use std::{fs, path::Component};
+
+pub fn pack_file(args: PackFileArgs) -> Result<()> {
+	let bytes =
+		fs::read(&args.file).wrap_err_with(|| format!("failed to read {}", args.file.display()))?;
+	let name = match args.name {
+		Some(name) => name,
+		None => args
+			.file
+			.file_name()
+			.ok_or_else(|| eyre!("{} has no file name", args.file.display()))?
+			.into(),
+	};
+	if name
+		.components()
+		.any(|component| !matches!(component, Component::Normal(_)))
+	{
+		bail!(
+			"invalid name {}: it must be a relative path without '..'",
+			name.display()
+		);
+	}
+	let executable = args.executable || is_executable::is_executable(&args.file);
+	let archive = AsarWriter::single(&name, bytes, executable)
+		.wrap_err_with(|| format!("failed to pack {}", args.file.display()))?;
+	fs::write(&args.output, archive)
+		.wrap_err_with(|| format!("failed to write asar to {}", args.output.display()))?;
+	Ok(())
+}
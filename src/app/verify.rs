@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use super::args::VerifyArgs;
+use asar::{AsarReader, Manifest};
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use std::{collections::BTreeMap, fs, path::Path};
+
+pub fn verify(args: VerifyArgs, read_unpacked: bool) -> Result<()> {
+	let file = fs::read(&args.archive)
+		.wrap_err_with(|| format!("failed to read archive {}", args.archive.display()))?;
+	let asar_path = if read_unpacked {
+		Some(args.archive)
+	} else {
+		None
+	};
+	let reader = AsarReader::new(&file, asar_path).wrap_err("failed to read archive")?;
+
+	let mut problems = 0;
+	// Files in the manifest which don't have a hash to check them against.
+	let mut unchecked = 0;
+	for (_, result) in reader.verify_iter() {
+		if let Err(err) = result {
+			println!("invalid: {err}");
+			problems += 1;
+		}
+	}
+
+	if let Some(manifest_path) = &args.manifest {
+		let manifest: Manifest =
+			serde_json::from_slice(&fs::read(manifest_path).wrap_err_with(|| {
+				format!("failed to read manifest {}", manifest_path.display())
+			})?)
+			.wrap_err_with(|| format!("failed to parse manifest {}", manifest_path.display()))?;
+		let expected = manifest
+			.files
+			.iter()
+			.map(|file| (file.path.as_path(), file))
+			.collect::<BTreeMap<&Path, _>>();
+		for (path, expected) in &expected {
+			let Some(file) = reader.files().get(*path) else {
+				println!("missing: {}", path.display());
+				problems += 1;
+				continue;
+			};
+			let Some(expected_hash) = &expected.hash else {
+				eprintln!(
+					"warning: {} has no hash in the manifest, so its contents weren't checked",
+					path.display()
+				);
+				unchecked += 1;
+				continue;
+			};
+			let hash = expected
+				.algorithm
+				.unwrap_or(args.algorithm)
+				.hash(file.stored_data());
+			if hash != *expected_hash {
+				println!(
+					"mismatch: {} (expected {}, got {})",
+					path.display(),
+					hex::encode(expected_hash),
+					hex::encode(&hash)
+				);
+				problems += 1;
+			}
+		}
+		for path in reader.files().keys() {
+			if !expected.contains_key(path.as_path()) {
+				println!("extra: {}", path.display());
+				problems += 1;
+			}
+		}
+
+		let expected_links = manifest
+			.symlinks
+			.iter()
+			.map(|symlink| (symlink.path.as_path(), symlink.link.as_path()))
+			.collect::<BTreeMap<&Path, &Path>>();
+		for (path, expected_link) in &expected_links {
+			let Some(link) = reader.symlinks().get(*path) else {
+				println!("missing: {}", path.display());
+				problems += 1;
+				continue;
+			};
+			if link != expected_link {
+				println!(
+					"mismatch: {} (expected a link to {}, got a link to {})",
+					path.display(),
+					expected_link.display(),
+					link.display()
+				);
+				problems += 1;
+			}
+		}
+		for path in reader.symlinks().keys() {
+			if !expected_links.contains_key(path.as_path()) {
+				println!("extra: {}", path.display());
+				problems += 1;
+			}
+		}
+	}
+
+	if problems > 0 {
+		return Err(eyre!("found {problems} problems"));
+	}
+	if unchecked > 0 {
+		println!(
+			"all {} files are valid, but {unchecked} weren't checked against the manifest",
+			reader.files().len()
+		);
+	} else {
+		println!("all {} files are valid", reader.files().len());
+	}
+	Ok(())
+}
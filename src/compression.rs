@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+#[cfg(feature = "compression")]
+use crate::header::CompressionMethod;
+use crate::{
+	error::{Error, Result},
+	header::Compression,
+};
+#[cfg(feature = "compression")]
+use flate2::{read::GzDecoder, write::GzEncoder};
+#[cfg(feature = "compression")]
+use std::io::ErrorKind;
+use std::{
+	io::{Read, Write},
+	path::Path,
+};
+
+/// The most memory that's allocated up front for decompressing a file, as its
+/// original size comes from the header, and can't be trusted.
+#[cfg(feature = "compression")]
+const MAX_PREALLOCATION: usize = 16 * 1024 * 1024;
+
+/// Compresses the contents of a file with `method`.
+#[cfg(feature = "compression")]
+pub(crate) fn compress(method: CompressionMethod, data: &[u8]) -> Result<Vec<u8>> {
+	match method {
+		CompressionMethod::Gzip => {
+			let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+			encoder.write_all(data)?;
+			Ok(encoder.finish()?)
+		}
+	}
+}
+
+/// Decompresses the contents of the file at `path`, checking that they're the
+/// size they were before they were compressed.
+pub(crate) fn decompress(path: &Path, data: &[u8], compression: &Compression) -> Result<Vec<u8>> {
+	#[cfg(feature = "compression")]
+	let mut decompressed = Vec::with_capacity(compression.original_size.min(MAX_PREALLOCATION));
+	#[cfg(not(feature = "compression"))]
+	let mut decompressed = Vec::new();
+	decompress_to(path, data, compression, &mut decompressed)?;
+	Ok(decompressed)
+}
+
+/// Decompresses the contents of the file at `path` from `reader` into
+/// `writer`, returning the amount of bytes written.
+///
+/// No more than one byte past the original size is ever decompressed, so a
+/// file which decompresses to far more than its header says can't use up all
+/// the memory or disk space.
+#[cfg(feature = "compression")]
+pub(crate) fn decompress_to(
+	path: &Path,
+	reader: impl Read,
+	compression: &Compression,
+	writer: &mut impl Write,
+) -> Result<u64> {
+	let invalid = |reason: String| Error::Decompression {
+		path: path.to_path_buf(),
+		reason,
+	};
+	let original_size = compression.original_size as u64;
+	let mut decoder = match compression.method {
+		CompressionMethod::Gzip => GzDecoder::new(reader).take(original_size.saturating_add(1)),
+	};
+	let mut buffer = [0; 8192];
+	let mut written = 0;
+	loop {
+		let read = match decoder.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(read) => read,
+			Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+			Err(err) => return Err(invalid(err.to_string())),
+		};
+		written += read as u64;
+		if written > original_size {
+			return Err(invalid(format!("expected {original_size} bytes, got more")));
+		}
+		writer.write_all(&buffer[..read])?;
+	}
+	if written != original_size {
+		return Err(invalid(format!(
+			"expected {original_size} bytes, got {written}"
+		)));
+	}
+	Ok(written)
+}
+
+/// Without the `compression` feature, compressed files can't be read, so this
+/// always fails rather than returning their compressed contents.
+#[cfg(not(feature = "compression"))]
+pub(crate) fn decompress_to(
+	path: &Path,
+	_reader: impl Read,
+	_compression: &Compression,
+	_writer: &mut impl Write,
+) -> Result<u64> {
+	Err(Error::Decompression {
+		path: path.to_path_buf(),
+		reason: "the `compression` feature isn't enabled".to_owned(),
+	})
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod test {
+	use super::{compress, decompress};
+	use crate::{
+		error::Error,
+		header::{Compression, CompressionMethod},
+	};
+	use std::path::Path;
+
+	#[test]
+	fn test_decompress_original_size() {
+		let data = b"Don't Panic. ".repeat(1000);
+		let compressed = compress(CompressionMethod::Gzip, &data).expect("failed to compress");
+		let decompress = |original_size| {
+			decompress(Path::new("advice.txt"), &compressed, &Compression {
+				method: CompressionMethod::Gzip,
+				original_size,
+			})
+		};
+		assert_eq!(decompress(data.len()), Ok(data.clone()));
+		for original_size in [0, 10, data.len() - 1, data.len() + 1, usize::MAX] {
+			assert!(
+				matches!(decompress(original_size), Err(Error::Decompression { .. })),
+				"{original_size} should be the wrong size"
+			);
+		}
+	}
+}
@@ -1,7 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
+use crate::reader::EntryKind;
 use serde::de::Error as DeError;
 use serde_json::Error as JsonError;
-use std::{io::Error as IoError, path::PathBuf};
+use std::{io::Error as IoError, ops::Range, path::PathBuf};
 use thiserror::Error as ThisError;
 
 #[derive(Debug, ThisError)]
@@ -14,23 +15,94 @@ pub enum Error {
 	Json(#[from] JsonError),
 	#[error("Archive is truncated")]
 	Truncated,
+	#[error("Archive is truncated within its header")]
+	HeaderTruncated,
 	#[error(
 		"Hash mismatch in file '{}'{}. Expected: {}, got: {}",
 		.file.display(),
-		.block.map(|block| format!(", block #{}", block)).unwrap_or_default(),
+		.block.map(|block| format!(", block #{} (bytes {}..{})", block, .byte_range.start, .byte_range.end)).unwrap_or_default(),
 		hex::encode(.expected),
 		hex::encode(.actual)
 	)]
 	HashMismatch {
 		file: PathBuf,
 		block: Option<usize>,
+		/// The range of bytes in the file which failed to match the hash.
+		byte_range: Range<usize>,
 		expected: Vec<u8>,
 		actual: Vec<u8>,
 	},
 	#[error("File '{}' has already been written", .0.display())]
 	FileAlreadyWritten(PathBuf),
+	#[error("Can't write a {} to '{}', as there's already a {} there", .new_kind, .path.display(), .existing_kind)]
+	PathConflict {
+		path: PathBuf,
+		existing_kind: EntryKind,
+		new_kind: EntryKind,
+	},
 	#[error("Invalid hash algorithm: '{}'", .0)]
 	InvalidHashAlgorithm(String),
+	#[error("Invalid glob '{}': {}", .glob, .err)]
+	InvalidGlob { glob: String, err: String },
+	#[error("Symbolic link '{}' points outside of the packed directory: '{}'", .path.display(), .link.display())]
+	SymlinkOutsideRoot { path: PathBuf, link: PathBuf },
+	#[error("Extracting would write outside of the destination: '{}'", .0.display())]
+	EscapesDestination(PathBuf),
+	#[error("Path '{}' can't be created on Windows", .0.display())]
+	ReservedName(PathBuf),
+	#[error("File '{}' was not found in the archive", .0.display())]
+	FileNotFound(PathBuf),
+	#[error("Invalid integrity for file '{}': {}", .path.display(), .reason)]
+	InvalidIntegrity { path: PathBuf, reason: String },
+	#[error("Too many levels of symbolic links while resolving '{}'", .0.display())]
+	SymlinkLoop(PathBuf),
+	#[error("Entry '{}' appears more than once in the archive", .0.display())]
+	DuplicateEntry(PathBuf),
+	#[error(
+		"Invalid hash length{}: expected {} bytes, got {}",
+		.block.map(|block| format!(" for block #{}", block)).unwrap_or_default(),
+		.expected,
+		.actual
+	)]
+	InvalidHashLength {
+		block: Option<usize>,
+		expected: usize,
+		actual: usize,
+	},
+	#[error("Path '{}' isn't valid UTF-8", .0.display())]
+	NonUtf8Path(PathBuf),
+	#[error("The root of the archive is a {0}, rather than a directory")]
+	InvalidRoot(EntryKind),
+	#[error("Failed to decompress file '{}': {}", .path.display(), .reason)]
+	Decompression { path: PathBuf, reason: String },
+	#[error("Unknown field '{}' in the header entry for '{}'", .field, .path.display())]
+	UnknownField { path: PathBuf, field: String },
+	#[error("Header is {size} bytes, which is more than the limit of {max} bytes")]
+	HeaderTooLarge { size: usize, max: usize },
+	#[error("Archive has {count} entries, which is more than the limit of {max}")]
+	TooManyEntries { count: usize, max: usize },
+	#[error(
+		"File '{}' is {} bytes, which is more than the limit of {} bytes",
+		.path.display(),
+		.size,
+		.max
+	)]
+	FileTooLarge {
+		path: PathBuf,
+		size: usize,
+		max: usize,
+	},
+	#[error(
+		"File '{}' at offset {} with size {} overflows the address space",
+		.path.display(),
+		.offset,
+		.size
+	)]
+	OffsetOverflow {
+		path: PathBuf,
+		offset: usize,
+		size: usize,
+	},
 }
 
 impl Clone for Error {
@@ -43,19 +115,85 @@ impl Clone for Error {
 			},
 			Self::Json(json_err) => Self::Json(JsonError::custom(json_err.to_string())),
 			Self::Truncated => Self::Truncated,
+			Self::HeaderTruncated => Self::HeaderTruncated,
 			Self::HashMismatch {
 				file,
 				block,
+				byte_range,
 				expected,
 				actual,
 			} => Self::HashMismatch {
 				file: file.clone(),
 				block: *block,
+				byte_range: byte_range.clone(),
 				expected: expected.clone(),
 				actual: actual.clone(),
 			},
 			Self::FileAlreadyWritten(path) => Self::FileAlreadyWritten(path.clone()),
+			Self::PathConflict {
+				path,
+				existing_kind,
+				new_kind,
+			} => Self::PathConflict {
+				path: path.clone(),
+				existing_kind: *existing_kind,
+				new_kind: *new_kind,
+			},
 			Self::InvalidHashAlgorithm(alg) => Self::InvalidHashAlgorithm(alg.clone()),
+			Self::InvalidGlob { glob, err } => Self::InvalidGlob {
+				glob: glob.clone(),
+				err: err.clone(),
+			},
+			Self::SymlinkOutsideRoot { path, link } => Self::SymlinkOutsideRoot {
+				path: path.clone(),
+				link: link.clone(),
+			},
+			Self::EscapesDestination(path) => Self::EscapesDestination(path.clone()),
+			Self::ReservedName(path) => Self::ReservedName(path.clone()),
+			Self::FileNotFound(path) => Self::FileNotFound(path.clone()),
+			Self::InvalidIntegrity { path, reason } => Self::InvalidIntegrity {
+				path: path.clone(),
+				reason: reason.clone(),
+			},
+			Self::SymlinkLoop(path) => Self::SymlinkLoop(path.clone()),
+			Self::DuplicateEntry(path) => Self::DuplicateEntry(path.clone()),
+			Self::InvalidHashLength {
+				block,
+				expected,
+				actual,
+			} => Self::InvalidHashLength {
+				block: *block,
+				expected: *expected,
+				actual: *actual,
+			},
+			Self::NonUtf8Path(path) => Self::NonUtf8Path(path.clone()),
+			Self::InvalidRoot(kind) => Self::InvalidRoot(*kind),
+			Self::Decompression { path, reason } => Self::Decompression {
+				path: path.clone(),
+				reason: reason.clone(),
+			},
+			Self::UnknownField { path, field } => Self::UnknownField {
+				path: path.clone(),
+				field: field.clone(),
+			},
+			Self::HeaderTooLarge { size, max } => Self::HeaderTooLarge {
+				size: *size,
+				max: *max,
+			},
+			Self::TooManyEntries { count, max } => Self::TooManyEntries {
+				count: *count,
+				max: *max,
+			},
+			Self::FileTooLarge { path, size, max } => Self::FileTooLarge {
+				path: path.clone(),
+				size: *size,
+				max: *max,
+			},
+			Self::OffsetOverflow { path, offset, size } => Self::OffsetOverflow {
+				path: path.clone(),
+				offset: *offset,
+				size: *size,
+			},
 		}
 	}
 }
@@ -87,34 +225,153 @@ impl PartialEq for Error {
 					&& json_err.to_string() == other_json_err.to_string()
 			}
 			(Self::Truncated, Self::Truncated) => true,
+			(Self::HeaderTruncated, Self::HeaderTruncated) => true,
 			(
 				Self::HashMismatch {
 					file,
 					block,
+					byte_range,
 					expected,
 					actual,
 				},
 				Self::HashMismatch {
 					file: other_file,
 					block: other_block,
+					byte_range: other_byte_range,
 					expected: other_expected,
 					actual: other_actual,
 				},
 			) => {
 				file == other_file
 					&& block == other_block
+					&& byte_range == other_byte_range
 					&& expected == other_expected
 					&& actual == other_actual
 			}
 			(Self::FileAlreadyWritten(path), Self::FileAlreadyWritten(other_path)) => {
 				path == other_path
 			}
+			(
+				Self::PathConflict {
+					path,
+					existing_kind,
+					new_kind,
+				},
+				Self::PathConflict {
+					path: other_path,
+					existing_kind: other_existing_kind,
+					new_kind: other_new_kind,
+				},
+			) => {
+				path == other_path
+					&& existing_kind == other_existing_kind
+					&& new_kind == other_new_kind
+			}
 			(Self::InvalidHashAlgorithm(alg), Self::InvalidHashAlgorithm(other_alg)) => {
 				alg == other_alg
 			}
+			(
+				Self::InvalidGlob { glob, err },
+				Self::InvalidGlob {
+					glob: other_glob,
+					err: other_err,
+				},
+			) => glob == other_glob && err == other_err,
+			(
+				Self::SymlinkOutsideRoot { path, link },
+				Self::SymlinkOutsideRoot {
+					path: other_path,
+					link: other_link,
+				},
+			) => path == other_path && link == other_link,
+			(Self::EscapesDestination(path), Self::EscapesDestination(other_path)) => {
+				path == other_path
+			}
+			(Self::ReservedName(path), Self::ReservedName(other_path)) => path == other_path,
+			(Self::FileNotFound(path), Self::FileNotFound(other_path)) => path == other_path,
+			(
+				Self::InvalidIntegrity { path, reason },
+				Self::InvalidIntegrity {
+					path: other_path,
+					reason: other_reason,
+				},
+			) => path == other_path && reason == other_reason,
+			(Self::SymlinkLoop(path), Self::SymlinkLoop(other_path)) => path == other_path,
+			(Self::DuplicateEntry(path), Self::DuplicateEntry(other_path)) => path == other_path,
+			(
+				Self::InvalidHashLength {
+					block,
+					expected,
+					actual,
+				},
+				Self::InvalidHashLength {
+					block: other_block,
+					expected: other_expected,
+					actual: other_actual,
+				},
+			) => block == other_block && expected == other_expected && actual == other_actual,
+			(Self::NonUtf8Path(path), Self::NonUtf8Path(other_path)) => path == other_path,
+			(Self::InvalidRoot(kind), Self::InvalidRoot(other_kind)) => kind == other_kind,
+			(
+				Self::Decompression { path, reason },
+				Self::Decompression {
+					path: other_path,
+					reason: other_reason,
+				},
+			) => path == other_path && reason == other_reason,
+			(
+				Self::UnknownField { path, field },
+				Self::UnknownField {
+					path: other_path,
+					field: other_field,
+				},
+			) => path == other_path && field == other_field,
+			(
+				Self::HeaderTooLarge { size, max },
+				Self::HeaderTooLarge {
+					size: other_size,
+					max: other_max,
+				},
+			) => size == other_size && max == other_max,
+			(
+				Self::TooManyEntries { count, max },
+				Self::TooManyEntries {
+					count: other_count,
+					max: other_max,
+				},
+			) => count == other_count && max == other_max,
+			(
+				Self::FileTooLarge { path, size, max },
+				Self::FileTooLarge {
+					path: other_path,
+					size: other_size,
+					max: other_max,
+				},
+			) => path == other_path && size == other_size && max == other_max,
+			(
+				Self::OffsetOverflow { path, offset, size },
+				Self::OffsetOverflow {
+					path: other_path,
+					offset: other_offset,
+					size: other_size,
+				},
+			) => path == other_path && offset == other_offset && size == other_size,
 			_ => false,
 		}
 	}
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// An error from reading an archive which may only be partially available,
+/// as returned by [`AsarReader::try_new`](crate::AsarReader::try_new).
+#[derive(Debug, Clone, PartialEq, ThisError)]
+pub enum PartialError {
+	/// The archive can't be read yet, and at least `required_len` bytes of it
+	/// are needed before it can get further.
+	#[error("Archive is incomplete, at least {required_len} bytes are needed")]
+	NeedMore { required_len: usize },
+	/// The archive is invalid.
+	#[error(transparent)]
+	Error(#[from] Error),
+}
@@ -0,0 +1,732 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use crate::{
+	error::{Error, Result},
+	reader::AsarReader,
+	streaming::StreamingAsarReader,
+};
+use filetime::FileTime;
+use std::{
+	collections::BTreeMap,
+	ffi::OsString,
+	fs,
+	io::{BufWriter, Read, Seek, Write},
+	path::{Component, Path, PathBuf},
+	time::SystemTime,
+};
+
+/// Names which refer to devices on Windows, and as such can't be used as file
+/// names, even with an extension.
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+	"CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+	"COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Options for [`AsarReader::extract_to`] and
+/// [`StreamingAsarReader::extract_to`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractOptions {
+	/// Strip this prefix from the start of each entry, skipping entries that
+	/// don't start with it.
+	pub strip_prefix: Option<PathBuf>,
+	/// How to handle paths that can't be created on Windows.
+	pub windows_names: WindowsNames,
+	/// Create symbolic links whose targets are outside of the destination,
+	/// such as links to absolute system paths, instead of failing with an
+	/// [`Error::EscapesDestination`].
+	///
+	/// Only set this for trusted archives, as an untrusted one could use such
+	/// a link to point at anything on the system. Files and directories are
+	/// never written outside of the destination, even when this is set.
+	pub unsafe_links: bool,
+}
+
+/// How to handle paths that can't be created on Windows, such as `aux` or
+/// `con`, names ending with a dot or space, or names containing characters
+/// like `:` or `?`.
+///
+/// Archives authored on Linux or macOS can contain such paths, as nothing
+/// prevents it there.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum WindowsNames {
+	/// Extract paths as-is.
+	#[default]
+	Allow,
+	/// Fail with an [`Error::ReservedName`] if a path can't be created on
+	/// Windows.
+	Reject,
+	/// Rename offending path components so that they can be created on
+	/// Windows, replacing invalid characters with `_`, and prefixing reserved
+	/// names with `_`.
+	Sanitize,
+}
+
+impl<'a> AsarReader<'a> {
+	/// Extracts all the directories, files, and symbolic links in the asar to
+	/// the `destination` directory.
+	///
+	/// If the archive recorded the modification times of its files, they're
+	/// restored.
+	///
+	/// ## Errors
+	///
+	///  - If an entry would be written outside of `destination`, or a symbolic
+	///    link points outside of it without [`ExtractOptions::unsafe_links`],
+	///    returns an [`Error::EscapesDestination`]
+	///  - If [`ExtractOptions::windows_names`] is [`WindowsNames::Reject`] and
+	///    a path can't be created on Windows, returns an
+	///    [`Error::ReservedName`]
+	///  - If writing fails, an [`Error::Io`] is returned.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{extract::ExtractOptions, AsarReader};
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// asar.extract_to("archive", &ExtractOptions::default())?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn extract_to(
+		&self,
+		destination: impl AsRef<Path>,
+		options: &ExtractOptions,
+	) -> Result<()> {
+		self.extract_to_with(destination, options, |path| Some(path.to_path_buf()))
+	}
+
+	/// Extracts the asar to the `destination` directory like
+	/// [`AsarReader::extract_to`], routing each entry through `map` first.
+	///
+	/// `map` is given the path of each entry in the archive, and returns the
+	/// path it should be extracted to, relative to `destination`, or `None` to
+	/// skip it. The returned path is then handled according to `options`, so
+	/// entries still can't be written outside of `destination`.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarReader::extract_to`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{extract::ExtractOptions, AsarReader};
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// asar.extract_to_with("archive", &ExtractOptions::default(), |path| {
+	/// 	let mut path = path.to_path_buf();
+	/// 	if path.extension().is_none() {
+	/// 		path.set_extension("bin");
+	/// 	}
+	/// 	Some(path)
+	/// })?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn extract_to_with(
+		&self,
+		destination: impl AsRef<Path>,
+		options: &ExtractOptions,
+		mut map: impl FnMut(&Path) -> Option<PathBuf>,
+	) -> Result<()> {
+		let destination = destination.as_ref();
+		let mut out_path = |path: &Path| match map(path) {
+			Some(mapped) => options.out_path(destination, &mapped),
+			None => Ok(None),
+		};
+		create_directories(self.directories().keys(), &mut out_path)?;
+		for (path, file) in self.files() {
+			let Some(out_path) = out_path(path)? else {
+				continue;
+			};
+			create_parent(&out_path)?;
+			fs::write(&out_path, file.data())?;
+			if file.executable() {
+				set_executable(&out_path)?;
+			}
+			if let Some(mtime) = file.mtime() {
+				set_mtime(&out_path, mtime)?;
+			}
+		}
+		create_symlinks(self.symlinks(), &mut out_path, options)
+	}
+}
+
+impl<R: Read + Seek> StreamingAsarReader<R> {
+	/// Extracts all the directories, files, and symbolic links in the asar to
+	/// the `destination` directory.
+	///
+	/// Each file is copied straight from the source to its output file, so the
+	/// whole archive is never held in memory. Unpacked files are skipped.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarReader::extract_to`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{extract::ExtractOptions, StreamingAsarReader};
+	/// use std::fs::File;
+	///
+	/// let mut reader = StreamingAsarReader::new(File::open("archive.asar")?)?;
+	/// reader.extract_to("archive", &ExtractOptions::default())?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn extract_to(
+		&mut self,
+		destination: impl AsRef<Path>,
+		options: &ExtractOptions,
+	) -> Result<()> {
+		self.extract_to_with(destination, options, |path| Some(path.to_path_buf()))
+	}
+
+	/// Extracts the asar to the `destination` directory like
+	/// [`StreamingAsarReader::extract_to`], routing each entry through `map`
+	/// first.
+	///
+	/// See [`AsarReader::extract_to_with`] for how `map` is used.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarReader::extract_to`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{extract::ExtractOptions, StreamingAsarReader};
+	/// use std::{fs::File, path::Path};
+	///
+	/// let mut reader = StreamingAsarReader::new(File::open("archive.asar")?)?;
+	/// reader.extract_to_with("archive", &ExtractOptions::default(), |path| {
+	/// 	path.starts_with("resources").then(|| path.to_path_buf())
+	/// })?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn extract_to_with(
+		&mut self,
+		destination: impl AsRef<Path>,
+		options: &ExtractOptions,
+		mut map: impl FnMut(&Path) -> Option<PathBuf>,
+	) -> Result<()> {
+		let destination = destination.as_ref();
+		let mut out_path = |path: &Path| match map(path) {
+			Some(mapped) => options.out_path(destination, &mapped),
+			None => Ok(None),
+		};
+		create_directories(self.directories().keys(), &mut out_path)?;
+		let paths = self
+			.files()
+			.iter()
+			.filter(|(_, file)| !file.unpacked())
+			.map(|(path, file)| (path.clone(), file.executable(), file.mtime()))
+			.collect::<Vec<_>>();
+		for (path, executable, mtime) in paths {
+			let Some(out_path) = out_path(&path)? else {
+				continue;
+			};
+			create_parent(&out_path)?;
+			let mut out = BufWriter::new(fs::File::create(&out_path)?);
+			self.copy_file_to(&path, &mut out)?;
+			out.flush()?;
+			drop(out);
+			if executable {
+				set_executable(&out_path)?;
+			}
+			if let Some(mtime) = mtime {
+				set_mtime(&out_path, mtime)?;
+			}
+		}
+		create_symlinks(self.symlinks(), &mut out_path, options)
+	}
+}
+
+impl ExtractOptions {
+	/// Works out where an entry of the archive should be extracted to, within
+	/// `destination`, or `None` if it should be skipped.
+	///
+	/// This is used by [`AsarReader::extract_to`], and can be used to extract
+	/// individual entries in the same way.
+	///
+	/// ## Errors
+	///
+	///  - If the entry would be written outside of `destination`, returns an
+	///    [`Error::EscapesDestination`]
+	///  - If [`ExtractOptions::windows_names`] is [`WindowsNames::Reject`] and
+	///    the path can't be created on Windows, returns an
+	///    [`Error::ReservedName`]
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// use asar::extract::ExtractOptions;
+	/// use std::path::Path;
+	///
+	/// let options = ExtractOptions::default();
+	/// let out_path = options.out_path(Path::new("out"), Path::new("dir/file.txt"))?;
+	/// assert_eq!(out_path.as_deref(), Some(Path::new("out/dir/file.txt")));
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn out_path(&self, destination: &Path, path: &Path) -> Result<Option<PathBuf>> {
+		let path = match &self.strip_prefix {
+			Some(prefix) => match path.strip_prefix(prefix) {
+				Ok(path) => path,
+				Err(_) => return Ok(None),
+			},
+			None => path,
+		};
+		let path = match self.windows_names {
+			WindowsNames::Allow => path.to_path_buf(),
+			WindowsNames::Reject => match sanitize_windows_path(path) {
+				sanitized if sanitized == path => sanitized,
+				_ => return Err(Error::ReservedName(path.to_path_buf())),
+			},
+			WindowsNames::Sanitize => sanitize_windows_path(path),
+		};
+		// `..` components are resolved before checking, as they'd otherwise
+		// pass the check while pointing elsewhere, and absolute paths replace
+		// the destination entirely when joined.
+		let out_path = normalize_path(&destination.join(path));
+		if !out_path.starts_with(normalize_path(destination)) {
+			return Err(Error::EscapesDestination(out_path));
+		}
+		Ok(Some(out_path))
+	}
+
+	/// Works out what a symbolic link extracted into `destination` should
+	/// point to, like [`ExtractOptions::out_path`], but allowing targets
+	/// outside of `destination` if [`ExtractOptions::unsafe_links`] is set.
+	///
+	/// `link` is the target as stored in the archive, relative to its root.
+	///
+	/// ## Errors
+	///
+	/// See [`ExtractOptions::out_path`].
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// use asar::extract::ExtractOptions;
+	/// use std::path::Path;
+	///
+	/// let options = ExtractOptions {
+	/// 	unsafe_links: true,
+	/// 	..ExtractOptions::default()
+	/// };
+	/// let target = options.link_target(Path::new("out"), Path::new("../lib"))?;
+	/// assert_eq!(target.as_deref(), Some(Path::new("lib")));
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn link_target(&self, destination: &Path, link: &Path) -> Result<Option<PathBuf>> {
+		self.allow_unsafe_link(self.out_path(destination, link))
+	}
+
+	/// Turns an [`Error::EscapesDestination`] for a link's target into the
+	/// target itself, if [`ExtractOptions::unsafe_links`] is set.
+	fn allow_unsafe_link(&self, target: Result<Option<PathBuf>>) -> Result<Option<PathBuf>> {
+		match target {
+			Err(Error::EscapesDestination(target)) if self.unsafe_links => Ok(Some(target)),
+			target => target,
+		}
+	}
+}
+
+/// Marks a file as executable by everyone who can read it. This does nothing
+/// on platforms without permission bits.
+fn set_executable(path: &Path) -> Result<()> {
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+
+		let mut permissions = fs::metadata(path)?.permissions();
+		let mode = permissions.mode();
+		permissions.set_mode(mode | (mode & 0o444) >> 2);
+		fs::set_permissions(path, permissions)?;
+	}
+	#[cfg(not(unix))]
+	let _ = path;
+	Ok(())
+}
+
+fn set_mtime(path: &Path, mtime: SystemTime) -> Result<()> {
+	filetime::set_file_mtime(path, FileTime::from_system_time(mtime))?;
+	Ok(())
+}
+
+fn create_directories<'p>(
+	directories: impl IntoIterator<Item = &'p PathBuf>,
+	out_path: &mut impl FnMut(&Path) -> Result<Option<PathBuf>>,
+) -> Result<()> {
+	for path in directories {
+		let Some(out_path) = out_path(path)? else {
+			continue;
+		};
+		if !out_path.exists() {
+			fs::create_dir_all(&out_path)?;
+		}
+	}
+	Ok(())
+}
+
+/// Creates the directory an entry is extracted into, if it doesn't exist.
+///
+/// Directories are already created from the archive's directory listing, but
+/// this covers headers which list entries without their parent directories.
+fn create_parent(out_path: &Path) -> Result<()> {
+	match out_path.parent() {
+		Some(parent) if !parent.exists() => Ok(fs::create_dir_all(parent)?),
+		_ => Ok(()),
+	}
+}
+
+fn create_symlinks(
+	symlinks: &BTreeMap<PathBuf, PathBuf>,
+	out_path: &mut impl FnMut(&Path) -> Result<Option<PathBuf>>,
+	options: &ExtractOptions,
+) -> Result<()> {
+	for (path, link) in symlinks {
+		let out_link = options.allow_unsafe_link(out_path(link))?;
+		let (Some(out_path), Some(out_link)) = (out_path(path)?, out_link) else {
+			continue;
+		};
+		create_parent(&out_path)?;
+		#[cfg(unix)]
+		{
+			std::os::unix::fs::symlink(out_link, &out_path)?;
+		}
+		#[cfg(windows)]
+		{
+			std::os::windows::fs::symlink_file(out_link, &out_path)?;
+		}
+	}
+	Ok(())
+}
+
+/// Lexically resolves the `.` and `..` components of a path, without touching
+/// the file system.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+	let mut normalized = PathBuf::new();
+	for component in path.components() {
+		match component {
+			Component::CurDir => {}
+			Component::ParentDir => match normalized.components().next_back() {
+				Some(Component::Normal(_)) => {
+					normalized.pop();
+				}
+				Some(Component::RootDir | Component::Prefix(_)) => {}
+				_ => normalized.push(".."),
+			},
+			other => normalized.push(other),
+		}
+	}
+	normalized
+}
+
+/// Renames each component of the path so that it can be created on Windows.
+fn sanitize_windows_path(path: &Path) -> PathBuf {
+	path.components()
+		.map(|component| match component {
+			Component::Normal(name) => sanitize_windows_name(&name.to_string_lossy()).into(),
+			other => other.as_os_str().to_owned(),
+		})
+		.collect::<Vec<OsString>>()
+		.iter()
+		.collect()
+}
+
+fn sanitize_windows_name(name: &str) -> String {
+	let mut sanitized = name
+		.chars()
+		.map(|c| match c {
+			'<' | '>' | ':' | '"' | '|' | '?' | '*' | '\\' => '_',
+			c if c.is_control() => '_',
+			c => c,
+		})
+		.collect::<String>();
+	if sanitized.ends_with(['.', ' ']) {
+		sanitized.pop();
+		sanitized.push('_');
+	}
+	let stem = sanitized.split('.').next().unwrap_or_default().trim_end();
+	if WINDOWS_RESERVED_NAMES
+		.iter()
+		.any(|reserved| stem.eq_ignore_ascii_case(reserved))
+	{
+		sanitized.insert(0, '_');
+	}
+	sanitized
+}
+
+#[cfg(test)]
+mod test {
+	use super::{normalize_path, sanitize_windows_path, ExtractOptions, WindowsNames};
+	use crate::{
+		error::Error,
+		header::{Header, TEST_ASAR},
+		reader::AsarReader,
+		test_util::temp_path,
+	};
+	use std::path::Path;
+	#[cfg(feature = "write")]
+	use std::time::{Duration, UNIX_EPOCH};
+
+	#[test]
+	fn test_sanitize_windows_path() {
+		let cases = [
+			("folder/file.txt", "folder/file.txt"),
+			("aux", "_aux"),
+			("folder/Con.txt", "folder/_Con.txt"),
+			("lpt1 .tar.gz", "_lpt1 .tar.gz"),
+			("console.log", "console.log"),
+			("trailing dot.", "trailing dot_"),
+			("trailing space ", "trailing space_"),
+			("what?/a:b*c", "what_/a_b_c"),
+		];
+		for (path, expected) in cases {
+			assert_eq!(sanitize_windows_path(Path::new(path)), Path::new(expected));
+		}
+	}
+
+	#[test]
+	fn test_normalize_path() {
+		let cases = [
+			("out/a/../b", "out/b"),
+			("out/./a", "out/a"),
+			("out/../../etc/passwd", "../etc/passwd"),
+			("/out/../../etc", "/etc"),
+			("./out", "out"),
+		];
+		for (path, expected) in cases {
+			assert_eq!(normalize_path(Path::new(path)), Path::new(expected));
+		}
+	}
+
+	#[test]
+	fn test_escapes_destination() {
+		let options = ExtractOptions::default();
+		let destination = Path::new("out");
+		assert_eq!(
+			options.out_path(destination, Path::new("folder/../file.txt")),
+			Ok(Some("out/file.txt".into()))
+		);
+		assert_eq!(
+			options.out_path(destination, Path::new("../../etc/passwd")),
+			Err(Error::EscapesDestination("../etc/passwd".into()))
+		);
+		assert_eq!(
+			options.out_path(destination, Path::new("folder/../../../etc/passwd")),
+			Err(Error::EscapesDestination("../etc/passwd".into()))
+		);
+		#[cfg(unix)]
+		assert_eq!(
+			options.out_path(destination, Path::new("/etc/passwd")),
+			Err(Error::EscapesDestination("/etc/passwd".into()))
+		);
+	}
+
+	#[test]
+	#[cfg(all(unix, feature = "write"))]
+	fn test_reject_escaping_symlinks() {
+		for link in ["../../etc/passwd", "/etc/passwd"] {
+			let mut writer = crate::writer::AsarWriter::new();
+			writer
+				.write_symlink("passwd", link)
+				.expect("failed to write symlink");
+			let mut out = Vec::new();
+			writer.finalize(&mut out).expect("failed to finalize asar");
+			let reader = AsarReader::new(&out, None).expect("failed to read asar");
+			let destination = temp_path("reject-escaping-symlinks");
+			let result = reader.extract_to(&destination, &ExtractOptions::default());
+			let _ = std::fs::remove_dir_all(&destination);
+			assert!(
+				matches!(result, Err(Error::EscapesDestination(_))),
+				"{link} should be rejected"
+			);
+		}
+	}
+
+	#[test]
+	#[cfg(all(unix, feature = "write"))]
+	fn test_unsafe_links() {
+		let mut writer = crate::writer::AsarWriter::new();
+		writer
+			.write_symlink("passwd", "/etc/passwd")
+			.expect("failed to write symlink");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		let destination = temp_path("unsafe-links");
+		let result = reader.extract_to(&destination, &ExtractOptions {
+			unsafe_links: true,
+			..ExtractOptions::default()
+		});
+		let target = std::fs::read_link(destination.join("passwd"));
+		let _ = std::fs::remove_dir_all(&destination);
+		result.expect("unsafe links should be allowed");
+		assert_eq!(
+			target.expect("failed to read link"),
+			Path::new("/etc/passwd")
+		);
+	}
+
+	#[test]
+	fn test_extract_to() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let destination = temp_path("extract-to");
+		reader
+			.extract_to(&destination, &ExtractOptions {
+				strip_prefix: Some("folder 1".into()),
+				windows_names: WindowsNames::Reject,
+				..ExtractOptions::default()
+			})
+			.expect("failed to extract asar");
+		let contents = std::fs::read(destination.join("folder 2/file 4.txt"))
+			.expect("failed to read extracted file");
+		assert_eq!(
+			contents,
+			reader.files()[Path::new("folder 1/folder 2/file 4.txt")].data()
+		);
+		assert!(!destination.join("file 1.txt").exists());
+		std::fs::remove_dir_all(&destination).expect("failed to clean up");
+	}
+
+	#[test]
+	fn test_extract_to_with() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let destination = temp_path("extract-to-with");
+		reader
+			.extract_to_with(&destination, &ExtractOptions::default(), |path| {
+				if path.starts_with("folder 1") {
+					return None;
+				}
+				let mut path = path.to_path_buf();
+				if path.extension().is_some() {
+					path.set_extension("bak");
+				}
+				Some(path)
+			})
+			.expect("failed to extract asar");
+		let contents =
+			std::fs::read(destination.join("file 1.bak")).expect("failed to read extracted file");
+		assert_eq!(contents, reader.files()[Path::new("file 1.txt")].data());
+		assert!(!destination.join("file 1.txt").exists());
+		assert!(!destination.join("folder 1").exists());
+		std::fs::remove_dir_all(&destination).expect("failed to clean up");
+
+		let result = reader.extract_to_with(&destination, &ExtractOptions::default(), |path| {
+			Some(Path::new("..").join(path))
+		});
+		let _ = std::fs::remove_dir_all(&destination);
+		assert!(matches!(result, Err(Error::EscapesDestination(_))));
+	}
+
+	#[test]
+	fn test_extract_missing_parents() {
+		let header: Header = serde_json::from_str(
+			r#"{"files":{"a/b/c.txt":{"size":3,"offset":"0"},"d/e":{"link":"a/b/c.txt"}}}"#,
+		)
+		.expect("failed to parse header");
+		let data = b"abc";
+		let reader =
+			AsarReader::new_from_header(header, 0, data, None).expect("failed to read asar");
+		assert!(reader.read_dir(Path::new("a")).is_none());
+		let destination = temp_path("extract-missing-parents");
+		reader
+			.extract_to(&destination, &ExtractOptions::default())
+			.expect("failed to extract asar");
+		let contents =
+			std::fs::read(destination.join("a/b/c.txt")).expect("failed to read extracted file");
+		assert_eq!(contents, data);
+		#[cfg(unix)]
+		assert!(destination.join("d/e").is_symlink());
+		std::fs::remove_dir_all(&destination).expect("failed to clean up");
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_reject_windows_names() {
+		let mut writer = crate::writer::AsarWriter::new();
+		writer
+			.write_file("folder/nul.txt", b"", false)
+			.expect("failed to write file");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		let destination = temp_path("reject-windows-names");
+		let result = reader.extract_to(&destination, &ExtractOptions {
+			windows_names: WindowsNames::Reject,
+			..ExtractOptions::default()
+		});
+		let _ = std::fs::remove_dir_all(&destination);
+		assert_eq!(result, Err(Error::ReservedName("folder/nul.txt".into())));
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_restore_mtimes() {
+		let mtime = UNIX_EPOCH + Duration::from_millis(1_234_567_890_123);
+		let mut writer = crate::writer::AsarWriter::new();
+		writer
+			.write_file_with_mtime("dated.txt", b"Don't Panic.", false, mtime)
+			.expect("failed to write file");
+		writer
+			.write_file("undated.txt", b"Mostly harmless.", false)
+			.expect("failed to write file");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.files()[Path::new("dated.txt")].mtime(), Some(mtime));
+		assert_eq!(reader.files()[Path::new("undated.txt")].mtime(), None);
+
+		let destination = temp_path("restore-mtimes");
+		reader
+			.extract_to(&destination, &ExtractOptions::default())
+			.expect("failed to extract asar");
+		let restored = std::fs::metadata(destination.join("dated.txt"))
+			.and_then(|metadata| metadata.modified())
+			.expect("failed to read mtime");
+		std::fs::remove_dir_all(&destination).expect("failed to clean up");
+		assert_eq!(restored, mtime);
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_executable_round_trip() {
+		let mut writer = crate::writer::AsarWriter::new();
+		writer
+			.write_file("run.sh", b"#!/bin/sh", true)
+			.expect("failed to write file");
+		writer
+			.write_file("data.txt", b"42", false)
+			.expect("failed to write file");
+		let bytes = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+		assert!(reader.files()[Path::new("run.sh")].executable());
+		assert!(!reader.files()[Path::new("data.txt")].executable());
+
+		let destination = temp_path("executable-round-trip");
+		reader
+			.extract_to(&destination, &ExtractOptions::default())
+			.expect("failed to extract asar");
+		#[cfg(unix)]
+		{
+			assert!(is_executable::is_executable(destination.join("run.sh")));
+			assert!(!is_executable::is_executable(destination.join("data.txt")));
+		}
+		std::fs::remove_dir_all(&destination).expect("failed to clean up");
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_mtimes_are_opt_in() {
+		let mut writer = crate::writer::AsarWriter::new();
+		writer
+			.write_file("undated.txt", b"Mostly harmless.", false)
+			.expect("failed to write file");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		assert!(!String::from_utf8_lossy(&out).contains("mtime"));
+	}
+}
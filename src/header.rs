@@ -1,13 +1,21 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
-use crate::error::{Error, Result};
+use crate::{
+	error::{Error, Result},
+	reader::EntryKind,
+};
 use byteorder::{LittleEndian, ReadBytesExt};
-use serde::{Deserialize, Serialize};
-use serde_with::{hex::Hex, serde_as, DisplayFromStr};
+use serde::{
+	de::{Error as _, IgnoredAny},
+	Deserialize, Deserializer, Serialize,
+};
+use serde_json::Value;
+use serde_with::{hex::Hex, serde_as, DisplayFromStr, PickFirst};
 use std::{
-	collections::HashMap,
+	collections::{BTreeMap, HashMap},
 	fmt::{self, Display},
-	path::PathBuf,
+	path::{Component, Path, PathBuf},
 	str::FromStr,
+	time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 #[cfg(test)]
@@ -23,19 +31,69 @@ pub(crate) static TEST_ASAR: &[u8] = include_bytes!("../data/test.asar");
 #[serde(untagged)]
 pub enum Header {
 	File(File),
-	Directory { files: HashMap<String, Self> },
-	Link { link: PathBuf },
+	Link {
+		link: PathBuf,
+	},
+	// This comes last, as an empty directory can be written as `{}`, which
+	// would otherwise match every other variant too.
+	#[serde(deserialize_with = "deserialize_directory")]
+	Directory {
+		// Ordered by name, so that the same tree is always written the same way.
+		files: BTreeMap<String, Self>,
+	},
+}
+
+/// Deserializes the children of a [`Header::Directory`].
+///
+/// An empty directory may be written without `files`, so an object without it
+/// is only a directory if it has no other fields than `unpacked`. Otherwise,
+/// a malformed file would be read as an empty directory, rather than failing.
+fn deserialize_directory<'de, D: Deserializer<'de>>(
+	deserializer: D,
+) -> std::result::Result<BTreeMap<String, Header>, D::Error> {
+	#[derive(Deserialize)]
+	struct Directory {
+		files: Option<BTreeMap<String, Header>>,
+		#[serde(flatten)]
+		other: HashMap<String, IgnoredAny>,
+	}
+
+	let directory = Directory::deserialize(deserializer)?;
+	match directory.files {
+		Some(files) => Ok(files),
+		None if directory.other.keys().all(|field| field == "unpacked") => Ok(BTreeMap::new()),
+		None => Err(D::Error::custom(
+			"expected a directory with 'files', or an empty directory",
+		)),
+	}
 }
 
 impl Header {
-	pub(crate) fn new() -> Self {
+	pub(crate) const fn new() -> Self {
 		Self::Directory {
-			files: HashMap::new(),
+			files: BTreeMap::new(),
+		}
+	}
+
+	/// Checks that the root of the header is a directory, as the entries of an
+	/// archive are named by the directories containing them, and a link at the
+	/// root would have nothing to point to.
+	pub(crate) const fn check_root(&self) -> Result<()> {
+		match self {
+			Self::File(_) => Err(Error::InvalidRoot(EntryKind::File)),
+			Self::Link { .. } => Err(Error::InvalidRoot(EntryKind::Symlink)),
+			Self::Directory { .. } => Ok(()),
 		}
 	}
 
 	/// Reads the header from a reader.
 	///
+	/// ## Errors
+	///
+	///  - If the reader ends before the header does, returns an
+	///    [`Error::HeaderTruncated`]
+	///  - If the header isn't valid JSON, returns an [`Error::Json`]
+	///
 	/// ## Example
 	///
 	/// ```rust,no_run
@@ -50,20 +108,361 @@ impl Header {
 	/// # Ok::<(), asar::Error>(())
 	/// ```
 	pub fn read<Read: ReadBytesExt>(data: &mut Read) -> Result<(Self, usize)> {
-		data.read_u32::<LittleEndian>()?; // magic number or something idk
-		let header_size = data.read_u32::<LittleEndian>()? as usize;
-		data.read_u32::<LittleEndian>()?;
-		let json_size = data.read_u32::<LittleEndian>()? as usize;
+		Self::read_limited(data, None)
+	}
+
+	/// Reads the header from a reader, like [`Header::read`], also returning a
+	/// warning for each field that this crate doesn't know about.
+	///
+	/// Unknown fields are ignored by [`Header::read`], which lets archives
+	/// written by newer versions of Electron be read, but they may change how
+	/// the archive should be read. See [`Header::read_strict`] for rejecting
+	/// them instead.
+	///
+	/// ## Errors
+	///
+	/// See [`Header::read`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::Header;
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let (header, offset, warnings) = Header::read_with_warnings(&mut &asar_file[..])?;
+	/// for warning in warnings {
+	/// 	eprintln!("warning: {warning}");
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn read_with_warnings<Read: ReadBytesExt>(
+		data: &mut Read,
+	) -> Result<(Self, usize, Vec<String>)> {
+		let mut unknown = Vec::new();
+		let (header, offset) = Self::read_checked(data, &mut unknown)?;
+		let warnings = unknown
+			.into_iter()
+			.map(|(path, field)| format!("unknown field '{field}' in '{}'", path.display()))
+			.collect();
+		Ok((header, offset, warnings))
+	}
+
+	/// Reads the header from a reader, like [`Header::read`], but fails if it
+	/// has any fields that this crate doesn't know about.
+	///
+	/// ## Errors
+	///
+	///  - If the header has a field that this crate doesn't know about, returns
+	///    an [`Error::UnknownField`] for the first one
+	///  - See [`Header::read`] for the rest
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::Header;
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let (header, offset) = Header::read_strict(&mut &asar_file[..])?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn read_strict<Read: ReadBytesExt>(data: &mut Read) -> Result<(Self, usize)> {
+		let mut unknown = Vec::new();
+		let (header, offset) = Self::read_checked(data, &mut unknown)?;
+		match unknown.into_iter().next() {
+			Some((path, field)) => Err(Error::UnknownField { path, field }),
+			None => Ok((header, offset)),
+		}
+	}
+
+	/// Reads the header, recording the path and name of each unknown field in
+	/// `unknown`.
+	fn read_checked<Read: ReadBytesExt>(
+		data: &mut Read,
+		unknown: &mut Vec<(PathBuf, String)>,
+	) -> Result<(Self, usize)> {
+		let (bytes, offset) = Self::read_raw(data)?;
+		let value: Value = serde_json::from_slice(&bytes)?;
+		find_unknown_fields(Path::new(""), &value, unknown);
+		Ok((serde_json::from_value(value)?, offset))
+	}
+
+	/// Reads the raw JSON of the header from a reader, without parsing it.
+	///
+	/// These are the exact bytes that Electron hashes to check the integrity of
+	/// the header. Like [`Header::read`], this also returns the offset the
+	/// header ends at.
+	///
+	/// ## Errors
+	///
+	///  - If the reader ends before the header does, returns an
+	///    [`Error::HeaderTruncated`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::Header;
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let (json, _) = Header::read_raw(&mut &asar_file[..])?;
+	/// println!("{}", String::from_utf8_lossy(&json));
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn read_raw<Read: ReadBytesExt>(data: &mut Read) -> Result<(Vec<u8>, usize)> {
+		Self::read_raw_limited(data, None)
+	}
+
+	/// Reads the header like [`Header::read`], failing with an
+	/// [`Error::HeaderTooLarge`] before the JSON is read if it's longer than
+	/// `max_size`.
+	pub(crate) fn read_limited<Read: ReadBytesExt>(
+		data: &mut Read,
+		max_size: Option<usize>,
+	) -> Result<(Self, usize)> {
+		let (bytes, offset) = Self::read_raw_limited(data, max_size)?;
+		Ok((serde_json::from_slice(&bytes)?, offset))
+	}
+
+	/// Reads the sizes at the start of an asar archive, which frame its
+	/// header, without reading the header itself.
+	///
+	/// This is meant for debugging archives which fail to parse; see
+	/// [`Framing`] for what each size should be.
+	///
+	/// ## Errors
+	///
+	///  - If the reader ends before the sizes do, returns an
+	///    [`Error::HeaderTruncated`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::Header;
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let framing = Header::read_framing(&mut &asar_file[..])?;
+	/// println!("{framing:?}");
+	/// // The inner pickle's payload is the JSON's length, then the padded JSON.
+	/// if framing.json_size > framing.json_string_size.saturating_sub(4) {
+	/// 	println!("the JSON is longer than the pickle containing it");
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn read_framing<Read: ReadBytesExt>(data: &mut Read) -> Result<Framing> {
+		let mut read = || {
+			data.read_u32::<LittleEndian>()
+				.map_err(|err| match err.kind() {
+					std::io::ErrorKind::UnexpectedEof => Error::HeaderTruncated,
+					_ => Error::Io(err),
+				})
+		};
+		Ok(Framing {
+			header_size: read()?,
+			payload_size: read()?,
+			json_string_size: read()?,
+			json_size: read()?,
+		})
+	}
+
+	fn read_raw_limited<Read: ReadBytesExt>(
+		data: &mut Read,
+		max_size: Option<usize>,
+	) -> Result<(Vec<u8>, usize)> {
+		let truncated = |err: std::io::Error| match err.kind() {
+			std::io::ErrorKind::UnexpectedEof => Error::HeaderTruncated,
+			_ => Error::Io(err),
+		};
+		let framing = Self::read_framing(data)?;
+		let header_size = framing.payload_size as usize;
+		let json_size = framing.json_size as usize;
+		if let Some(max) = max_size.filter(|max| json_size > *max) {
+			return Err(Error::HeaderTooLarge {
+				size: json_size,
+				max,
+			});
+		}
 		let mut bytes = vec![0_u8; json_size];
-		data.read_exact(&mut bytes)?;
-		Ok((serde_json::from_slice(&bytes)?, header_size + 8))
+		data.read_exact(&mut bytes).map_err(truncated)?;
+		Ok((bytes, header_size + 8))
+	}
+
+	/// Finds the node at `path` in the header, walking down its directories.
+	///
+	/// This is much cheaper than building an
+	/// [`AsarReader`](crate::reader::AsarReader) to check whether a path
+	/// exists, or to read the metadata of a single file. Symbolic links aren't
+	/// followed, and paths containing `..` aren't found. The empty path finds
+	/// the root directory.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::Header;
+	/// use std::{fs, path::Path};
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let (header, _) = Header::read(&mut &asar_file[..])?;
+	/// if let Some(Header::File(file)) = header.find(Path::new("dir/hello.txt")) {
+	/// 	println!("dir/hello.txt is {} bytes", file.size());
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn find(&self, path: &Path) -> Option<&Self> {
+		let mut node = self;
+		for component in path.components() {
+			match (component, node) {
+				(Component::Normal(name), Self::Directory { files }) => {
+					node = files.get(name.to_str()?)?;
+				}
+				(Component::CurDir | Component::RootDir, _) => {}
+				_ => return None,
+			}
+		}
+		Some(node)
+	}
+
+	/// Counts the files, directories, and symbolic links in the header, in a
+	/// single walk of the tree.
+	///
+	/// This is much cheaper than building an
+	/// [`AsarReader`](crate::reader::AsarReader), as no file contents are
+	/// touched. The root directory isn't counted.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::Header;
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let (header, _) = Header::read(&mut &asar_file[..])?;
+	/// let counts = header.count();
+	/// println!("There are {} files in archive.asar", counts.files);
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn count(&self) -> HeaderCounts {
+		let mut counts = HeaderCounts::default();
+		let mut stack = vec![self];
+		while let Some(header) = stack.pop() {
+			match header {
+				Self::File(_) => counts.files += 1,
+				Self::Directory { files } => {
+					counts.directories += 1;
+					stack.extend(files.values());
+				}
+				Self::Link { .. } => counts.symlinks += 1,
+			}
+		}
+		if let Self::Directory { .. } = self {
+			counts.directories -= 1;
+		}
+		counts
 	}
 }
 
+/// The fields of each kind of node in the header JSON that this crate knows
+/// about.
+const FILE_FIELDS: &[&str] = &[
+	"offset",
+	"unpacked",
+	"size",
+	"executable",
+	"integrity",
+	"mtime",
+	"asar-rs:compression",
+];
+// Electron marks directories as unpacked when all their contents are.
+const DIRECTORY_FIELDS: &[&str] = &["files", "unpacked"];
+const LINK_FIELDS: &[&str] = &["link"];
+const INTEGRITY_FIELDS: &[&str] = &["algorithm", "hash", "blockSize", "blocks"];
+
+/// Finds the fields of each node in the header JSON which aren't known about,
+/// recording the path of the node and the name of the field.
+///
+/// Nodes are told apart the same way as when deserializing a [`Header`].
+fn find_unknown_fields(path: &Path, value: &Value, unknown: &mut Vec<(PathBuf, String)>) {
+	let Value::Object(node) = value else {
+		return;
+	};
+	let known = if node.contains_key("size") {
+		FILE_FIELDS
+	} else if node.contains_key("link") {
+		LINK_FIELDS
+	} else {
+		DIRECTORY_FIELDS
+	};
+	for field in node.keys().filter(|field| !known.contains(&field.as_str())) {
+		unknown.push((path.to_path_buf(), field.clone()));
+	}
+	if let Some(Value::Object(integrity)) = node.get("integrity") {
+		for field in integrity
+			.keys()
+			.filter(|field| !INTEGRITY_FIELDS.contains(&field.as_str()))
+		{
+			unknown.push((path.to_path_buf(), format!("integrity.{field}")));
+		}
+	}
+	if let Some(Value::Object(files)) = node.get("files") {
+		for (name, child) in files {
+			find_unknown_fields(&path.join(name), child, unknown);
+		}
+	}
+}
+
+/// The sizes at the start of an asar archive, as returned by
+/// [`Header::read_framing`].
+///
+/// The header is framed like a Chromium pickle containing a pickle containing
+/// a string, so the sizes after the first are each 4 bytes smaller than the
+/// one before, and the data starts `payload_size + 8` bytes into the archive.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Framing {
+	/// The size of the outer pickle's header, which is always 4.
+	pub header_size: u32,
+	/// The size of the inner pickle, which is the header's length, aligned to
+	/// 4 bytes, plus 8.
+	pub payload_size: u32,
+	/// The inner pickle's payload size, which is the header's length, aligned
+	/// to 4 bytes, plus 4.
+	pub json_string_size: u32,
+	/// The length of the header's JSON, in bytes.
+	pub json_size: u32,
+}
+
+/// The amount of each kind of entry in a [`Header`], as returned by
+/// [`Header::count`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HeaderCounts {
+	/// The amount of files.
+	pub files: usize,
+	/// The amount of directories, not including the root.
+	pub directories: usize,
+	/// The amount of symbolic links.
+	pub symlinks: usize,
+}
+
+/// Where the contents of a [`File`] are stored.
+///
+/// Some non-standard archives have files with both an `offset` and
+/// `"unpacked": true`. Like Electron, these are read as unpacked, and the
+/// offset is ignored. An `offset` with `"unpacked": false` is read as an
+/// offset.
 #[serde_as]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum FileLocation {
+	/// This file is already unpacked from the asar archive.
+	// This comes first, so that it takes precedence over an offset.
+	Unpacked {
+		#[serde(
+			skip_serializing_if = "is_false",
+			deserialize_with = "deserialize_true"
+		)]
+		unpacked: bool,
+	},
 	/// This file is located in the asar archive, at an offset from the end of
 	/// the asar header.
 	Offset {
@@ -71,11 +470,6 @@ pub enum FileLocation {
 		#[serde_as(as = "DisplayFromStr")]
 		offset: usize,
 	},
-	/// This file is already unpacked from the asar archive.
-	Unpacked {
-		#[serde(skip_serializing_if = "is_false")]
-		unpacked: bool,
-	},
 }
 
 impl FileLocation {
@@ -93,20 +487,44 @@ impl FileLocation {
 /// This struct contains details about a file in an asar archive, such as
 /// where it is located in the archive, its size, whether its executable or not,
 /// and integrity details such as cryptographic hashes.
+#[serde_as]
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct File {
+	// The fields are in the same order as `@electron/asar` writes them, so
+	// that the header is written the same way.
+	/// The total size of the file.
+	///
+	/// Some non-standard archives store this as a string, which is accepted
+	/// when reading, but it's always written as a number.
+	#[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+	size: usize,
 	/// The location of the file - either at an offset in the asar archive, or
 	/// as an unpacked file.
 	#[serde(flatten)]
 	location: FileLocation,
-	/// The total size of the file.
-	size: usize,
-	/// Whether this file is executable or not.
-	#[serde(skip_serializing_if = "is_false", default = "default_false")]
-	executable: bool,
 	/// Integrity details of the file, such as hashes.
 	#[serde(skip_serializing_if = "Option::is_none")]
 	integrity: Option<FileIntegrity>,
+	/// Whether this file is executable or not.
+	#[serde(skip_serializing_if = "is_false", default = "default_false")]
+	executable: bool,
+	/// The modification time of the file, in milliseconds since the Unix
+	/// epoch.
+	///
+	/// This isn't part of the asar format, and is ignored by Electron. It's
+	/// only written when explicitly requested.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	mtime: Option<u64>,
+	/// How the contents of the file were compressed, if they were.
+	///
+	/// This isn't part of the asar format, so Electron will read compressed
+	/// files as they're stored.
+	#[serde(
+		rename = "asar-rs:compression",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	compression: Option<Compression>,
 }
 
 impl File {
@@ -121,6 +539,8 @@ impl File {
 			size,
 			executable,
 			integrity,
+			mtime: None,
+			compression: None,
 		}
 	}
 
@@ -129,6 +549,30 @@ impl File {
 		self.location
 	}
 
+	#[inline]
+	pub(crate) fn set_mtime(&mut self, mtime: SystemTime) {
+		self.mtime = mtime
+			.duration_since(UNIX_EPOCH)
+			.ok()
+			.and_then(|duration| u64::try_from(duration.as_millis()).ok());
+	}
+
+	#[inline]
+	#[cfg(feature = "compression")]
+	pub(crate) const fn set_compression(&mut self, compression: Compression) {
+		self.compression = Some(compression);
+	}
+
+	#[inline]
+	pub(crate) fn set_integrity(&mut self, integrity: FileIntegrity) {
+		self.integrity = Some(integrity);
+	}
+
+	#[inline]
+	pub(crate) const fn set_location(&mut self, location: FileLocation) {
+		self.location = location;
+	}
+
 	/// The offset from the end of the header that this file is located at.
 	///
 	/// If this returns `None`, then the file is 'unpacked', meaning it's not in
@@ -263,6 +707,89 @@ impl File {
 	pub const fn integrity(&self) -> Option<&FileIntegrity> {
 		self.integrity.as_ref()
 	}
+
+	/// The modification time of the file, if it was recorded when the archive
+	/// was written.
+	///
+	/// This isn't part of the asar format, so most archives won't have it.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use asar::Header;
+	/// # use std::fs;
+	/// #
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let (header, _) = Header::read(&mut &asar_file[..])?;
+	/// # let file = match header {
+	/// #     Header::File(file) => file,
+	/// #     _ => panic!("Not a file"),
+	/// # };
+	/// if let Some(mtime) = file.mtime() {
+	/// 	println!("File was last modified at {mtime:?}");
+	/// }
+	///
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub fn mtime(&self) -> Option<SystemTime> {
+		self.mtime
+			.map(|millis| UNIX_EPOCH + Duration::from_millis(millis))
+	}
+
+	/// How the contents of the file were compressed, if they were.
+	///
+	/// This isn't part of the asar format, and is only written by
+	/// `AsarWriterBuilder::compress`, with the `compression` feature.
+	/// When this is set, [`File::size`] is the size of the compressed
+	/// contents.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use asar::Header;
+	/// # use std::fs;
+	/// #
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let (header, _) = Header::read(&mut &asar_file[..])?;
+	/// # let file = match header {
+	/// #     Header::File(file) => file,
+	/// #     _ => panic!("Not a file"),
+	/// # };
+	/// if let Some(compression) = file.compression() {
+	/// 	println!("File is {} bytes uncompressed", compression.original_size);
+	/// }
+	///
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub const fn compression(&self) -> Option<&Compression> {
+		self.compression.as_ref()
+	}
+}
+
+/// How the contents of a file were compressed, as returned by
+/// [`File::compression`].
+///
+/// This is a non-standard extension to the asar format, which is stored under
+/// the `asar-rs:compression` key so that it doesn't clash with anything
+/// Electron might add.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Compression {
+	/// The method the contents were compressed with.
+	pub method: CompressionMethod,
+	/// The size of the contents before they were compressed.
+	pub original_size: usize,
+}
+
+/// A method of compressing the contents of a file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum CompressionMethod {
+	/// [gzip](https://en.wikipedia.org/wiki/Gzip) compression.
+	Gzip,
 }
 
 /// This struct contains the integrity details of a file, such as
@@ -278,14 +805,19 @@ pub struct FileIntegrity {
 	#[serde_as(as = "Hex")]
 	hash: Vec<u8>,
 	/// The size of each "block" to be hashed in a file.
+	#[serde(default = "default_block_size")]
 	block_size: usize,
 	/// The hash of each "block" in a file.
+	///
+	/// Small files are sometimes written without any block hashes, only the
+	/// whole-file hash.
 	#[serde_as(as = "Vec<Hex>")]
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	blocks: Vec<Vec<u8>>,
 }
 
 impl FileIntegrity {
-	pub(crate) fn new(
+	pub(crate) const fn new(
 		algorithm: HashAlgorithm,
 		hash: Vec<u8>,
 		block_size: usize,
@@ -299,6 +831,53 @@ impl FileIntegrity {
 		}
 	}
 
+	/// Creates integrity details from precomputed hashes, such as ones from a
+	/// build system, for use with `AsarWriter::write_file_with_integrity`, with
+	/// the `write` feature.
+	///
+	/// ## Errors
+	///
+	///  - If the hash, or the hash of any block, isn't the right length for the
+	///    algorithm, returns an [`Error::InvalidHashLength`]
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// use asar::{FileIntegrity, HashAlgorithm};
+	///
+	/// let data = b"So long, and thanks for all the fish.";
+	/// let hash = HashAlgorithm::Sha256.hash(data);
+	/// let integrity =
+	/// 	FileIntegrity::from_parts(HashAlgorithm::Sha256, hash.clone(), 4096, vec![hash])?;
+	/// assert_eq!(integrity.blocks().len(), 1);
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[cfg(feature = "integrity")]
+	pub fn from_parts(
+		algorithm: HashAlgorithm,
+		hash: Vec<u8>,
+		block_size: usize,
+		blocks: Vec<Vec<u8>>,
+	) -> Result<Self> {
+		let expected = algorithm.hash_len();
+		let check = |hash: &[u8], block: Option<usize>| {
+			if hash.len() == expected {
+				Ok(())
+			} else {
+				Err(Error::InvalidHashLength {
+					block,
+					expected,
+					actual: hash.len(),
+				})
+			}
+		};
+		check(&hash, None)?;
+		for (idx, block) in blocks.iter().enumerate() {
+			check(block, Some(idx + 1))?;
+		}
+		Ok(Self::new(algorithm, hash, block_size, blocks))
+	}
+
 	/// The hashing algorithm used to calculate the hash.
 	///
 	/// ## Example
@@ -378,6 +957,9 @@ impl FileIntegrity {
 
 	/// The hash of each "block" in a file.
 	///
+	/// This may be empty, if the archive only stored the hash of the whole
+	/// file.
+	///
 	/// ## Example
 	///
 	/// ```rust,no_run
@@ -417,14 +999,45 @@ pub enum HashAlgorithm {
 	Sha256,
 }
 
-impl Display for HashAlgorithm {
-	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl HashAlgorithm {
+	/// Every supported hashing algorithm.
+	///
+	/// ## Example
+	/// ```rust
+	/// use asar::HashAlgorithm;
+	///
+	/// for algorithm in HashAlgorithm::all() {
+	/// 	println!("{algorithm} is supported");
+	/// }
+	/// ```
+	#[inline]
+	pub const fn all() -> &'static [Self] {
+		&[Self::Sha256]
+	}
+
+	/// The name of the algorithm, as it's written in the header.
+	///
+	/// ## Example
+	/// ```rust
+	/// use asar::HashAlgorithm;
+	///
+	/// const NAME: &str = HashAlgorithm::Sha256.name();
+	/// assert_eq!(NAME, "SHA256");
+	/// ```
+	#[inline]
+	pub const fn name(&self) -> &'static str {
 		match self {
-			Self::Sha256 => write!(f, "SHA256"),
+			Self::Sha256 => "SHA256",
 		}
 	}
 }
 
+impl Display for HashAlgorithm {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
 impl FromStr for HashAlgorithm {
 	type Err = Error;
 
@@ -440,13 +1053,35 @@ const fn is_false(b: &bool) -> bool {
 	!*b
 }
 
+/// Deserializes a `bool`, failing unless it's `true`, so that an
+/// [`FileLocation::Unpacked`] is only matched by `"unpacked": true`.
+fn deserialize_true<'de, D: serde::Deserializer<'de>>(
+	deserializer: D,
+) -> std::result::Result<bool, D::Error> {
+	match bool::deserialize(deserializer)? {
+		true => Ok(true),
+		false => Err(serde::de::Error::custom("expected `unpacked` to be true")),
+	}
+}
+
 const fn default_false() -> bool {
 	false
 }
 
+pub(crate) const DEFAULT_BLOCK_SIZE: usize = 4 * 1024 * 1024; // 4 MiB
+
+const fn default_block_size() -> usize {
+	DEFAULT_BLOCK_SIZE
+}
+
 #[cfg(test)]
 mod test {
-	use super::{Header, TEST_ASAR};
+	use super::{
+		FileIntegrity, FileLocation, Framing, HashAlgorithm, Header, HeaderCounts,
+		DEFAULT_BLOCK_SIZE, TEST_ASAR,
+	};
+	use crate::{error::Error, reader::AsarReader, test_util::frame};
+	use std::path::Path;
 
 	static TEST_ASAR_JSON: &str = include_str!("../data/test.asar.json");
 
@@ -458,4 +1093,236 @@ mod test {
 			serde_json::from_str::<Header>(TEST_ASAR_JSON).expect("failed to decode expected");
 		assert_eq!(header, expected);
 	}
+
+	#[test]
+	pub fn test_read_framing() {
+		let framing = Header::read_framing(&mut &*TEST_ASAR).expect("failed to read framing");
+		let (json, offset) = Header::read_raw(&mut &*TEST_ASAR).expect("failed to read header");
+		let aligned = json.len().next_multiple_of(4) as u32;
+		assert_eq!(framing, Framing {
+			header_size: 4,
+			payload_size: aligned + 8,
+			json_string_size: aligned + 4,
+			json_size: json.len() as u32,
+		});
+		assert_eq!(offset, framing.payload_size as usize + 8);
+		assert_eq!(
+			Header::read_framing(&mut &TEST_ASAR[..15]),
+			Err(Error::HeaderTruncated)
+		);
+	}
+
+	#[test]
+	pub fn test_unpacked_with_offset() {
+		let file = |json| match serde_json::from_str::<Header>(json) {
+			Ok(Header::File(file)) => file,
+			other => panic!("{json} should be a file, got {other:?}"),
+		};
+		let both = file(r#"{"size":3,"offset":"8","unpacked":true}"#);
+		assert!(both.unpacked());
+		assert_eq!(both.location(), FileLocation::unpacked());
+		assert_eq!(both.offset(), None);
+		let packed = file(r#"{"size":3,"offset":"8","unpacked":false}"#);
+		assert!(!packed.unpacked());
+		assert_eq!(packed.offset(), Some(8));
+		let unpacked = file(r#"{"size":3,"unpacked":true}"#);
+		assert_eq!(unpacked.location(), FileLocation::unpacked());
+	}
+
+	#[test]
+	pub fn test_find() {
+		let (header, _) = Header::read(&mut &*TEST_ASAR).expect("failed to read header");
+		let Some(Header::File(file)) = header.find(Path::new("folder 1/folder 2/file 4.txt"))
+		else {
+			panic!("file 4.txt should be a file");
+		};
+		assert_eq!(file.size(), 20);
+		assert!(matches!(
+			header.find(Path::new("folder 1/folder 3")),
+			Some(Header::Directory { .. })
+		));
+		assert!(matches!(
+			header.find(Path::new("symlink 1")),
+			Some(Header::Link { .. })
+		));
+		assert_eq!(header.find(Path::new("")), Some(&header));
+		assert_eq!(header.find(Path::new("folder 1/missing.txt")), None);
+		assert_eq!(header.find(Path::new("file 1.txt/file 2.txt")), None);
+		assert_eq!(header.find(Path::new("folder 1/../file 1.txt")), None);
+	}
+
+	#[test]
+	pub fn test_read_raw() {
+		let (json, offset) = Header::read_raw(&mut &*TEST_ASAR).expect("failed to read header");
+		let (_, expected_offset) = Header::read(&mut &*TEST_ASAR).expect("failed to read header");
+		assert_eq!(offset, expected_offset);
+		assert_eq!(&TEST_ASAR[16..16 + json.len()], json.as_slice());
+	}
+
+	#[test]
+	pub fn test_empty_directory_without_files() {
+		let header: Header = serde_json::from_str(
+			r#"{"files":{"emptydir":{},"link":{"link":"emptydir"},"file":{"size":0,"offset":"0"}}}"#,
+		)
+		.expect("failed to parse header");
+		let Header::Directory { files } = header else {
+			panic!("root should be a directory");
+		};
+		assert_eq!(files["emptydir"], Header::Directory {
+			files: Default::default()
+		});
+		assert!(matches!(files["link"], Header::Link { .. }));
+		assert!(matches!(files["file"], Header::File(_)));
+	}
+
+	#[test]
+	pub fn test_malformed_file_is_not_a_directory() {
+		for json in [
+			r#"{"size":"oops","offset":"0"}"#,
+			r#"{"size":3,"unpacked":false}"#,
+			r#"{"offset":"0"}"#,
+			r#"{"files":{"bad":{"size":3,"unpacked":false}}}"#,
+		] {
+			assert!(
+				serde_json::from_str::<Header>(json).is_err(),
+				"{json} should be rejected"
+			);
+		}
+		for json in [
+			r#"{}"#,
+			r#"{"unpacked":true}"#,
+			r#"{"files":{},"unpacked":true}"#,
+		] {
+			assert_eq!(
+				serde_json::from_str::<Header>(json).expect("failed to parse header"),
+				Header::new(),
+				"{json} should be an empty directory"
+			);
+		}
+	}
+
+	#[test]
+	pub fn test_unknown_fields() {
+		let json = br#"{"files":{"dir":{"files":{"a.txt":{"size":1,"offset":"0","compressed":true,"integrity":{"algorithm":"SHA256","hash":"00","blockSize":4,"blocks":[],"salt":"x"}}},"unpacked":true},"link":{"link":"dir/a.txt","target":"file"}}}"#;
+		let asar = frame(json, &[]);
+
+		let (header, offset) = Header::read(&mut &asar[..]).expect("failed to read header");
+		let (lenient, lenient_offset, mut warnings) =
+			Header::read_with_warnings(&mut &asar[..]).expect("failed to read header");
+		assert_eq!((lenient, lenient_offset), (header, offset));
+		warnings.sort();
+		assert_eq!(warnings, vec![
+			"unknown field 'compressed' in 'dir/a.txt'",
+			"unknown field 'integrity.salt' in 'dir/a.txt'",
+			"unknown field 'target' in 'link'",
+		]);
+		assert!(matches!(
+			Header::read_strict(&mut &asar[..]),
+			Err(Error::UnknownField { .. })
+		));
+		Header::read_strict(&mut &*TEST_ASAR).expect("test asar should have no unknown fields");
+	}
+
+	#[test]
+	pub fn test_hash_algorithm_names() {
+		for algorithm in HashAlgorithm::all() {
+			assert_eq!(algorithm.name().parse::<HashAlgorithm>(), Ok(*algorithm));
+			assert_eq!(
+				serde_json::to_string(algorithm).unwrap(),
+				format!("\"{}\"", algorithm.name())
+			);
+		}
+	}
+
+	#[test]
+	pub fn test_header_truncated() {
+		for len in [0, 7, 16, 100] {
+			assert_eq!(
+				Header::read(&mut &TEST_ASAR[..len]).map(|_| ()),
+				Err(Error::HeaderTruncated),
+				"{len} bytes should be too short for the header"
+			);
+		}
+	}
+
+	#[test]
+	pub fn test_count() {
+		let (header, offset) = Header::read(&mut &*TEST_ASAR).expect("failed to read header");
+		let reader = AsarReader::new_from_header(header.clone(), offset, TEST_ASAR, None)
+			.expect("failed to read asar");
+		assert_eq!(header.count(), HeaderCounts {
+			files: reader.files().len(),
+			directories: reader.directories().len() - 1,
+			symlinks: reader.symlinks().len(),
+		});
+	}
+
+	#[test]
+	#[cfg(feature = "integrity")]
+	pub fn test_integrity_from_parts() {
+		let hash = HashAlgorithm::Sha256.hash(b"42");
+		assert!(
+			FileIntegrity::from_parts(
+				HashAlgorithm::Sha256,
+				hash.clone(),
+				4096,
+				vec![hash.clone()]
+			)
+			.is_ok()
+		);
+		assert_eq!(
+			FileIntegrity::from_parts(HashAlgorithm::Sha256, vec![0; 20], 4096, Vec::new()),
+			Err(Error::InvalidHashLength {
+				block: None,
+				expected: 32,
+				actual: 20,
+			})
+		);
+		assert_eq!(
+			FileIntegrity::from_parts(HashAlgorithm::Sha256, hash.clone(), 4096, vec![
+				hash,
+				vec![0; 64]
+			]),
+			Err(Error::InvalidHashLength {
+				block: Some(2),
+				expected: 32,
+				actual: 64,
+			})
+		);
+	}
+
+	#[test]
+	pub fn test_size_as_string() {
+		let header: Header =
+			serde_json::from_str(r#"{"files":{"a.txt":{"size":"1234","offset":"0"}}}"#)
+				.expect("failed to parse header");
+		let Header::Directory { files } = &header else {
+			panic!("root should be a directory");
+		};
+		let Some(Header::File(file)) = files.get("a.txt") else {
+			panic!("a.txt should be a file");
+		};
+		assert_eq!(file.size(), 1234);
+		assert_eq!(
+			serde_json::to_string(&header).expect("failed to serialize header"),
+			r#"{"files":{"a.txt":{"size":1234,"offset":"0"}}}"#
+		);
+	}
+
+	#[test]
+	pub fn test_integrity_without_blocks() {
+		let integrity = serde_json::from_str::<FileIntegrity>(
+			r#"{
+				"algorithm": "SHA256",
+				"hash": "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+			}"#,
+		)
+		.expect("failed to decode integrity");
+		assert_eq!(integrity.algorithm(), HashAlgorithm::Sha256);
+		assert_eq!(integrity.block_size(), DEFAULT_BLOCK_SIZE);
+		assert!(integrity.blocks().is_empty());
+
+		let json = serde_json::to_value(&integrity).expect("failed to encode integrity");
+		assert!(json.get("blocks").is_none());
+	}
 }
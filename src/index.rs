@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use crate::{
+	error::Result,
+	header::{File, Header},
+	reader::EntryKind,
+	streaming::recursive_read,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+};
+
+/// The structure of an asar archive, without the contents of any of its
+/// files, which can be serialized and loaded again later.
+///
+/// This answers the same questions about an archive's structure as an
+/// [`AsarReader`](crate::AsarReader), such as the size and integrity of each
+/// file, but without needing the archive itself.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use asar::{index::ArchiveIndex, AsarReader};
+/// use std::{fs, path::Path};
+///
+/// let asar_file = fs::read("archive.asar")?;
+/// let asar = AsarReader::new(&asar_file, None)?;
+/// fs::write("archive.json", serde_json::to_vec(&asar.index())?)?;
+///
+/// let index: ArchiveIndex = serde_json::from_slice(&fs::read("archive.json")?)?;
+/// let file = index.get(Path::new("hello.txt")).unwrap();
+/// println!("hello.txt is {} bytes", file.size());
+/// # Ok::<(), asar::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ArchiveIndex {
+	files: BTreeMap<PathBuf, File>,
+	directories: BTreeMap<PathBuf, Vec<PathBuf>>,
+	symlinks: BTreeMap<PathBuf, PathBuf>,
+}
+
+impl ArchiveIndex {
+	/// Builds an index of every entry in the given [`Header`].
+	///
+	/// ## Errors
+	///
+	///  - If the root of the header isn't a directory, returns an
+	///    [`Error::InvalidRoot`](crate::Error::InvalidRoot)
+	///  - If a path appears more than once, returns an
+	///    [`Error::DuplicateEntry`](crate::Error::DuplicateEntry)
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{index::ArchiveIndex, Header};
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let (header, _) = Header::read(&mut &asar_file[..])?;
+	/// let index = ArchiveIndex::from_header(&header)?;
+	/// println!("There are {} files in archive.asar", index.files().len());
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn from_header(header: &Header) -> Result<Self> {
+		header.check_root()?;
+		let mut index = Self::default();
+		recursive_read(
+			PathBuf::new(),
+			&mut index.files,
+			&mut index.directories,
+			&mut index.symlinks,
+			header,
+		)?;
+		Ok(index)
+	}
+
+	/// Gets all files in the archive.
+	#[inline]
+	pub const fn files(&self) -> &BTreeMap<PathBuf, File> {
+		&self.files
+	}
+
+	/// Gets all directories in the archive, and the paths of their contents.
+	#[inline]
+	pub const fn directories(&self) -> &BTreeMap<PathBuf, Vec<PathBuf>> {
+		&self.directories
+	}
+
+	/// Gets all symbolic links in the archive, and the paths they point to.
+	#[inline]
+	pub const fn symlinks(&self) -> &BTreeMap<PathBuf, PathBuf> {
+		&self.symlinks
+	}
+
+	/// Gets information about a file, without following symbolic links.
+	#[inline]
+	pub fn get(&self, path: &Path) -> Option<&File> {
+		self.files.get(path)
+	}
+
+	/// Gets the contents of a directory.
+	///
+	/// See [`AsarReader::read_dir`](crate::AsarReader::read_dir).
+	#[inline]
+	pub fn read_dir(&self, path: &Path) -> Option<&[PathBuf]> {
+		self.directories.get(path).map(Vec::as_slice)
+	}
+
+	/// Gets the kind of the entry at a path, or `None` if nothing exists at
+	/// that path.
+	///
+	/// See [`AsarReader::entry_kind`](crate::AsarReader::entry_kind).
+	pub fn entry_kind(&self, path: &Path) -> Option<EntryKind> {
+		if self.files.contains_key(path) {
+			Some(EntryKind::File)
+		} else if self.directories.contains_key(path) {
+			Some(EntryKind::Directory)
+		} else if self.symlinks.contains_key(path) {
+			Some(EntryKind::Symlink)
+		} else {
+			None
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::ArchiveIndex;
+	use crate::{header::TEST_ASAR, reader::AsarReader};
+	use std::path::Path;
+
+	#[test]
+	pub fn test_index_round_trip() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let index = reader.index();
+		let json = serde_json::to_string(&index).expect("failed to serialize index");
+		let loaded: ArchiveIndex = serde_json::from_str(&json).expect("failed to load index");
+		assert_eq!(loaded, index);
+
+		assert_eq!(loaded.files().len(), reader.files().len());
+		assert_eq!(loaded.directories(), reader.directories());
+		assert_eq!(loaded.symlinks(), reader.symlinks());
+		for (path, file) in reader.files() {
+			let indexed = loaded.get(path).expect("file should be indexed");
+			assert_eq!(indexed.size(), file.data().len());
+			assert_eq!(indexed.integrity(), file.integrity());
+			assert_eq!(loaded.entry_kind(path), reader.entry_kind(path));
+		}
+		assert_eq!(
+			loaded.read_dir(Path::new("folder 1")),
+			reader.read_dir(Path::new("folder 1"))
+		);
+	}
+}
@@ -1,9 +1,13 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
-use crate::header::HashAlgorithm;
+use crate::{
+	error::{Error, Result},
+	header::{FileIntegrity, HashAlgorithm},
+};
 use sha2::{
 	digest::{typenum::Unsigned, Digest, OutputSizeUser},
 	Sha256,
 };
+use std::path::Path;
 
 impl HashAlgorithm {
 	/// Returns the length of the output of the hash function.
@@ -59,11 +63,177 @@ impl HashAlgorithm {
 	/// );
 	/// ```
 	pub fn hash_blocks(&self, block_size: usize, data: &[u8]) -> Vec<Vec<u8>> {
-		let mut blocks = Vec::with_capacity((0..data.len()).step_by(block_size).size_hint().0);
+		let mut blocks = Vec::with_capacity(data.len().div_ceil(block_size));
 		data.chunks(block_size).for_each(|block| {
 			let hash = self.hash(block);
 			blocks.push(hash);
 		});
 		blocks
 	}
+
+	/// Hashes the given data, and each block of it, returning both the hash
+	/// of the whole data and the hashes of the blocks, as used by
+	/// [`FileIntegrity`].
+	///
+	/// If the data fits in a single block, it's only hashed once, as the hash
+	/// of the block is the same as the hash of the whole data.
+	///
+	/// ## Example
+	/// ```rust
+	/// use asar::HashAlgorithm;
+	///
+	/// let data = b"Time is an illusion. Lunchtime doubly so.";
+	/// let (hash, blocks) = HashAlgorithm::Sha256.hash_with_blocks(1024, data);
+	/// assert_eq!(hash, HashAlgorithm::Sha256.hash(data));
+	/// assert_eq!(blocks, vec![hash]);
+	/// ```
+	pub fn hash_with_blocks(&self, block_size: usize, data: &[u8]) -> (Vec<u8>, Vec<Vec<u8>>) {
+		let hash = self.hash(data);
+		if !data.is_empty() && data.len() <= block_size {
+			let blocks = vec![hash.clone()];
+			return (hash, blocks);
+		}
+		(hash, self.hash_blocks(block_size, data))
+	}
+
+	/// Like [`HashAlgorithm::hash_with_blocks`], but also appends the data to
+	/// `out`, going over it only once.
+	///
+	/// Each block is hashed and copied while it's still in the cache, rather
+	/// than hashing the whole data, then each block, then copying it.
+	pub(crate) fn copy_with_blocks(
+		&self,
+		block_size: usize,
+		data: &[u8],
+		out: &mut Vec<u8>,
+	) -> (Vec<u8>, Vec<Vec<u8>>) {
+		out.reserve(data.len());
+		if data.is_empty() || data.len() <= block_size {
+			let hash = self.hash(data);
+			out.extend_from_slice(data);
+			let blocks = if data.is_empty() {
+				Vec::new()
+			} else {
+				vec![hash.clone()]
+			};
+			return (hash, blocks);
+		}
+		let mut hasher = self.hasher();
+		let mut blocks = Vec::with_capacity(data.len().div_ceil(block_size));
+		for block in data.chunks(block_size) {
+			hasher.update(block);
+			blocks.push(self.hash(block));
+			out.extend_from_slice(block);
+		}
+		(hasher.finalize(), blocks)
+	}
+
+	/// Creates an incremental [`Hasher`] for this algorithm, for hashing data
+	/// that isn't all in memory at once.
+	pub(crate) fn hasher(&self) -> Hasher {
+		match self {
+			Self::Sha256 => Hasher::Sha256(Sha256::new()),
+		}
+	}
+}
+
+/// An in-progress hash, created with [`HashAlgorithm::hasher`].
+#[derive(Clone)]
+pub(crate) enum Hasher {
+	Sha256(Sha256),
+}
+
+impl Hasher {
+	/// Feeds more data into the hash.
+	pub(crate) fn update(&mut self, data: &[u8]) {
+		match self {
+			Self::Sha256(hasher) => hasher.update(data),
+		}
+	}
+
+	/// Finishes the hash, returning it.
+	pub(crate) fn finalize(self) -> Vec<u8> {
+		match self {
+			Self::Sha256(hasher) => hasher.finalize().to_vec(),
+		}
+	}
+}
+
+/// Checks that the block hashes of a file's integrity details are consistent
+/// with its size, without hashing anything, so that they can't under-verify
+/// the file.
+///
+/// Files may have no block hashes at all, in which case only the hash of the
+/// whole file is checked. Empty files may have either no blocks, or a single
+/// empty one.
+pub(crate) fn check_blocks(path: &Path, size: usize, integrity: &FileIntegrity) -> Result<()> {
+	let invalid = |reason: String| Error::InvalidIntegrity {
+		path: path.to_path_buf(),
+		reason,
+	};
+	let block_size = integrity.block_size();
+	if block_size == 0 {
+		return Err(invalid("block size is zero".to_string()));
+	}
+	let blocks = integrity.blocks().len();
+	let expected_blocks = size.div_ceil(block_size);
+	if blocks != 0 && blocks != expected_blocks && !(size == 0 && blocks == 1) {
+		return Err(invalid(format!(
+			"expected {expected_blocks} blocks of {block_size} bytes, found {blocks}"
+		)));
+	}
+	Ok(())
+}
+
+/// Checks the contents of a file against its integrity details, checking each
+/// block before the whole file, so that mismatches can be narrowed down.
+pub(crate) fn verify(path: &Path, data: &[u8], integrity: &FileIntegrity) -> Result<()> {
+	let algorithm = integrity.algorithm();
+	let block_size = integrity.block_size();
+	let blocks = integrity.blocks();
+	if block_size > 0 && !blocks.is_empty() {
+		for (idx, (block, expected_hash)) in data.chunks(block_size).zip(blocks.iter()).enumerate()
+		{
+			let hash = algorithm.hash(block);
+			if hash != *expected_hash {
+				let start = idx * block_size;
+				return Err(Error::HashMismatch {
+					file: path.to_path_buf(),
+					block: Some(idx + 1),
+					byte_range: start..start + block.len(),
+					expected: expected_hash.to_owned(),
+					actual: hash,
+				});
+			}
+		}
+	}
+	let hash = algorithm.hash(data);
+	if hash != integrity.hash() {
+		return Err(Error::HashMismatch {
+			file: path.to_path_buf(),
+			block: None,
+			byte_range: 0..data.len(),
+			expected: integrity.hash().to_owned(),
+			actual: hash,
+		});
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use crate::header::HashAlgorithm;
+
+	#[test]
+	fn test_copy_with_blocks() {
+		for len in [0, 1, 7, 8, 9, 24, 25] {
+			let data = (0..len).map(|n| n as u8).collect::<Vec<_>>();
+			let mut out = vec![0xff];
+			let (hash, blocks) = HashAlgorithm::Sha256.copy_with_blocks(8, &data, &mut out);
+			assert_eq!(out[0], 0xff);
+			assert_eq!(&out[1..], data.as_slice());
+			assert_eq!(hash, HashAlgorithm::Sha256.hash(&data));
+			assert_eq!(blocks, HashAlgorithm::Sha256.hash_blocks(8, &data));
+		}
+	}
 }
@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use crate::{
+	error::{Error, Result},
+	header::{File, FileLocation, Header},
+	streaming::{file_start, recursive_read},
+};
+use std::{
+	cell::{OnceCell, RefCell},
+	collections::BTreeMap,
+	fs,
+	io::{Read, Seek, SeekFrom},
+	path::{Path, PathBuf},
+};
+
+/// A LazyAsarReader reads the header of an asar archive up front, but only
+/// reads the contents of each file the first time it's requested, caching them
+/// afterwards.
+///
+/// This sits between [`AsarReader`](crate::AsarReader), which needs the whole
+/// archive in memory, and
+/// [`StreamingAsarReader`](crate::StreamingAsarReader), which reads files
+/// again on every access. It's a good fit when only a few files out of many are
+/// needed, but are needed repeatedly.
+///
+/// The archive is kept open for as long as the reader lives. As the file
+/// handle is shared between reads, a LazyAsarReader can't be shared between
+/// threads.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use asar::lazy::LazyAsarReader;
+/// use std::path::Path;
+///
+/// let reader = LazyAsarReader::open("archive.asar")?;
+/// let contents = reader.read(Path::new("hello.txt"))?;
+/// assert_eq!(contents, b"Hello, World!");
+/// # Ok::<(), asar::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct LazyAsarReader {
+	source: RefCell<fs::File>,
+	asar_path: PathBuf,
+	header: Header,
+	begin_offset: u64,
+	directories: BTreeMap<PathBuf, Vec<PathBuf>>,
+	files: BTreeMap<PathBuf, File>,
+	symlinks: BTreeMap<PathBuf, PathBuf>,
+	contents: BTreeMap<PathBuf, OnceCell<Vec<u8>>>,
+}
+
+impl LazyAsarReader {
+	/// Opens the asar archive at `path`, reading only its header.
+	///
+	/// Unpacked files are read from the `.asar.unpacked` directory next to the
+	/// archive.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::lazy::LazyAsarReader;
+	///
+	/// let reader = LazyAsarReader::open("archive.asar")?;
+	/// println!("There are {} files in archive.asar", reader.files().len());
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+		let asar_path = path.as_ref().to_path_buf();
+		let mut source = fs::File::open(&asar_path)?;
+		let (header, offset) = Header::read(&mut source)?;
+		header.check_root()?;
+		let mut files = BTreeMap::new();
+		let mut directories = BTreeMap::new();
+		let mut symlinks = BTreeMap::new();
+		recursive_read(
+			PathBuf::new(),
+			&mut files,
+			&mut directories,
+			&mut symlinks,
+			&header,
+		)?;
+		let contents = files
+			.keys()
+			.map(|path| (path.clone(), OnceCell::new()))
+			.collect();
+		Ok(Self {
+			source: RefCell::new(source),
+			asar_path,
+			header,
+			begin_offset: offset as u64,
+			directories,
+			files,
+			symlinks,
+			contents,
+		})
+	}
+
+	/// The [`Header`] of the asar.
+	#[inline]
+	pub const fn header(&self) -> &Header {
+		&self.header
+	}
+
+	/// Gets all files in the asar, and their details.
+	#[inline]
+	pub const fn files(&self) -> &BTreeMap<PathBuf, File> {
+		&self.files
+	}
+
+	/// Gets all directories in the asar, including empty ones.
+	///
+	/// The root directory is always listed, under an empty path.
+	#[inline]
+	pub const fn directories(&self) -> &BTreeMap<PathBuf, Vec<PathBuf>> {
+		&self.directories
+	}
+
+	/// Gets all symbolic links in the asar.
+	#[inline]
+	pub const fn symlinks(&self) -> &BTreeMap<PathBuf, PathBuf> {
+		&self.symlinks
+	}
+
+	/// Gets the contents of a file, reading them from the archive if this is
+	/// the first time they've been requested.
+	///
+	/// ## Errors
+	///
+	///  - If the file doesn't exist, returns an [`Error::FileNotFound`]
+	///  - If the file's offset and size overflow, returns an
+	///    [`Error::OffsetOverflow`]
+	///  - If the archive ends before the file does, returns an
+	///    [`Error::Truncated`]
+	///  - If the file is unpacked and can't be read, returns an
+	///    [`Error::UnpackedIoError`]
+	///  - If the file is compressed, and can't be decompressed, returns an
+	///    [`Error::Decompression`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::lazy::LazyAsarReader;
+	/// use std::path::Path;
+	///
+	/// let reader = LazyAsarReader::open("archive.asar")?;
+	/// let contents = reader.read(Path::new("hello.txt"))?;
+	/// assert_eq!(contents, b"Hello, World!");
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn read(&self, path: &Path) -> Result<&[u8]> {
+		let (Some(file), Some(cell)) = (self.files.get(path), self.contents.get(path)) else {
+			return Err(Error::FileNotFound(path.to_path_buf()));
+		};
+		if let Some(contents) = cell.get() {
+			return Ok(contents);
+		}
+		let contents = self.read_uncached(path, file)?;
+		#[cfg(feature = "check-integrity-on-read")]
+		if let Some(integrity) = file.integrity() {
+			crate::integrity::verify(path, &contents, integrity)?;
+		}
+		let contents = match file.compression() {
+			Some(compression) => crate::compression::decompress(path, &contents, compression)?,
+			None => contents,
+		};
+		Ok(cell.get_or_init(|| contents))
+	}
+
+	fn read_uncached(&self, path: &Path, file: &File) -> Result<Vec<u8>> {
+		match file.location() {
+			FileLocation::Offset { offset } => {
+				let size = file.size();
+				let start = file_start(self.begin_offset, path, offset, size)?;
+				let mut source = self.source.borrow_mut();
+				// The size comes from the header, so it's checked against what's
+				// left of the archive before anything is allocated for it.
+				if start + size as u64 > source.metadata()?.len() {
+					return Err(Error::Truncated);
+				}
+				source.seek(SeekFrom::Start(start))?;
+				let mut contents = Vec::with_capacity(size);
+				(&mut *source)
+					.take(size as u64)
+					.read_to_end(&mut contents)?;
+				if contents.len() != size {
+					return Err(Error::Truncated);
+				}
+				Ok(contents)
+			}
+			FileLocation::Unpacked { .. } => {
+				fs::read(self.asar_path.with_extension("asar.unpacked").join(path)).map_err(|err| {
+					Error::UnpackedIoError {
+						path: path.to_path_buf(),
+						err,
+					}
+				})
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::LazyAsarReader;
+	use crate::{
+		error::Error,
+		header::TEST_ASAR,
+		reader::AsarReader,
+		test_util::{frame, temp_path},
+	};
+	use std::{fs, path::Path};
+
+	#[test]
+	fn test_lazy_read() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let lazy = LazyAsarReader::open(concat!(env!("CARGO_MANIFEST_DIR"), "/data/test.asar"))
+			.expect("failed to open asar");
+		assert_eq!(lazy.files().len(), reader.files().len());
+		for (path, file) in reader.files() {
+			let contents = lazy.read(path).expect("failed to read file");
+			assert_eq!(contents, file.data());
+			// The second read comes from the cache, so it's the same buffer.
+			assert_eq!(
+				lazy.read(path).expect("failed to read file").as_ptr(),
+				contents.as_ptr()
+			);
+		}
+		assert_eq!(
+			lazy.read(Path::new("missing.txt")),
+			Err(Error::FileNotFound("missing.txt".into()))
+		);
+	}
+
+	#[test]
+	fn test_lazy_offset_overflow() {
+		let huge = 1_usize << 62;
+		let json = format!(
+			r#"{{"files":{{"overflow":{{"size":1,"offset":"{}"}},"huge":{{"size":{huge},"offset":"0"}}}}}}"#,
+			u64::MAX
+		);
+		let path = temp_path("lazy-offset-overflow.asar");
+		fs::write(&path, frame(json.as_bytes(), b"abc")).expect("failed to write asar");
+		let lazy = LazyAsarReader::open(&path).expect("failed to open asar");
+		assert_eq!(
+			lazy.read(Path::new("overflow")),
+			Err(Error::OffsetOverflow {
+				path: "overflow".into(),
+				offset: usize::MAX,
+				size: 1
+			})
+		);
+		assert_eq!(lazy.read(Path::new("huge")), Err(Error::Truncated));
+		fs::remove_file(&path).expect("failed to clean up");
+	}
+}
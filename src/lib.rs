@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
-#![forbid(unsafe_code)]
+// Memory maps need `unsafe`, which is only allowed in the `mmap` module.
+#![cfg_attr(not(feature = "mmap"), forbid(unsafe_code))]
+#![cfg_attr(feature = "mmap", deny(unsafe_code))]
 #![warn(
 	clippy::perf,
 	clippy::complexity,
@@ -22,7 +24,7 @@
 //!
 //! fn main() -> Result<()> {
 //! 	let asar_file = fs::read("archive.asar")?;
-//! 	let asar = AsarReader::new(&asar_file, None)?;
+//! 	let asar = AsarReader::from_bytes(&asar_file)?;
 //!
 //! 	println!("There are {} files in archive.asar", asar.files().len());
 //! 	for path in asar.files().keys() {
@@ -39,7 +41,7 @@
 //!
 //! fn main() -> Result<()> {
 //! 	let asar_file = fs::read("archive.asar")?;
-//! 	let asar = AsarReader::new(&asar_file, None)?;
+//! 	let asar = AsarReader::from_bytes(&asar_file)?;
 //!
 //! 	let path = PathBuf::from("hello.txt");
 //! 	let file = asar.files().get(&path).unwrap();
@@ -69,26 +71,65 @@
 //!    archive, failing if any integrity check fails.
 //!  - `write` - Enable writing an asar archive. **Enabled by default**, also
 //!    enables `integrity`.
+//!  - `normalize-unicode`: Enable normalizing paths to Unicode NFC when
+//!    writing, and matching paths regardless of their normalization when
+//!    reading.
+//!  - `compression`: Enable compressing files with gzip when writing, and
+//!    decompressing them when reading. Compressed archives can't be read by
+//!    Electron, and without this feature, reading a compressed file fails.
+//!  - `remote`: Enable `remote::RemoteAsarReader`, which reads archives over
+//!    HTTP with `Range` requests.
+//!  - `mmap`: Enable `AsarReader::open_mmap_at`, which reads an archive from a
+//!    memory map of a file. Only available on Unix.
 //!
 //! # License
 //!
 //! `asar` is licensed under either the [MIT license](LICENSE-MIT) or the
 //! [Apache License 2.0](LICENSE-APACHE), at the choice of the user.
 
+mod compression;
 /// Error handling for parsing, reading, and writing asar archives.
 pub mod error;
+/// Extracting asar archives to the file system.
+pub mod extract;
 /// Header parsing for asar archives.
 pub mod header;
+/// Serializable indexes of the structure of asar archives, without their
+/// contents.
+pub mod index;
 #[cfg(feature = "integrity")]
 pub mod integrity;
+/// Reading asar archives from the file system, only reading files when
+/// they're needed.
+pub mod lazy;
+/// Flat, serializable descriptions of asar archives.
+pub mod manifest;
+#[cfg(all(feature = "mmap", unix))]
+/// Reading asar archives from memory maps.
+pub mod mmap;
+/// Parsing ordering files, which control the layout of packed archives.
+pub mod ordering;
 /// Reading asar archives.
 pub mod reader;
+#[cfg(feature = "remote")]
+/// Reading asar archives over HTTP, without downloading them entirely.
+pub mod remote;
+/// Reading asar archives from a stream, without loading them into memory.
+pub mod streaming;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "normalize-unicode")]
+mod unicode;
 #[cfg(feature = "write")]
 /// Writing asar archives.
 pub mod writer;
 
 pub use error::{Error, Result};
 pub use header::{File, FileIntegrity, HashAlgorithm, Header};
+pub use index::ArchiveIndex;
+pub use lazy::LazyAsarReader;
+pub use manifest::Manifest;
 pub use reader::AsarReader;
+pub use streaming::StreamingAsarReader;
 #[cfg(feature = "write")]
 pub use writer::AsarWriter;
@@ -11,6 +11,9 @@ fn main() -> Result<()> {
 
 	match args.subcommand {
 		AppSubcommand::Pack(subargs) => app::pack::pack(subargs).wrap_err("failed to pack archive"),
+		AppSubcommand::PackFile(subargs) => {
+			app::pack_file::pack_file(subargs).wrap_err("failed to pack file")
+		}
 		AppSubcommand::List(subargs) => {
 			app::list::list(subargs, args.read_unpacked).wrap_err("failed to list archive")
 		}
@@ -21,5 +24,11 @@ fn main() -> Result<()> {
 			app::extract_file::extract_file(subargs, args.read_unpacked)
 				.wrap_err("failed to extract file from archive")
 		}
+		AppSubcommand::Header(subargs) => {
+			app::header::header(subargs).wrap_err("failed to read archive header")
+		}
+		AppSubcommand::Verify(subargs) => {
+			app::verify::verify(subargs, args.read_unpacked).wrap_err("failed to verify archive")
+		}
 	}
 }
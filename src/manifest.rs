@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use crate::header::{HashAlgorithm, Header};
+use serde::{Deserialize, Serialize};
+use serde_with::{hex::Hex, serde_as};
+use std::path::{Path, PathBuf};
+
+/// A flat description of every entry in an asar archive.
+///
+/// Unlike the nested [`Header`], this is a simple list of files and symbolic
+/// links, which is easy to store alongside an archive or to feed to other
+/// tools.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use asar::AsarReader;
+/// use std::fs;
+///
+/// let asar_file = fs::read("archive.asar")?;
+/// let asar = AsarReader::new(&asar_file, None)?;
+/// let manifest = asar.manifest();
+/// println!("{}", serde_json::to_string_pretty(&manifest)?);
+/// # Ok::<(), asar::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Manifest {
+	/// Every file in the archive, sorted by path.
+	pub files: Vec<ManifestFile>,
+	/// Every symbolic link in the archive, sorted by path.
+	pub symlinks: Vec<ManifestSymlink>,
+}
+
+/// A file listed in a [`Manifest`].
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ManifestFile {
+	/// The path of the file, relative to the root of the archive.
+	pub path: PathBuf,
+	/// The size of the file, in bytes.
+	pub size: usize,
+	/// The offset of the file from the end of the header, or `None` if the
+	/// file is unpacked.
+	pub offset: Option<usize>,
+	/// Whether this file is executable or not.
+	pub executable: bool,
+	/// Whether this file is unpacked or not.
+	pub unpacked: bool,
+	/// The hashing algorithm used to calculate [`ManifestFile::hash`].
+	pub algorithm: Option<HashAlgorithm>,
+	/// The hash of the file, in hex format.
+	#[serde_as(as = "Option<Hex>")]
+	pub hash: Option<Vec<u8>>,
+}
+
+/// A symbolic link listed in a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ManifestSymlink {
+	/// The path of the symbolic link, relative to the root of the archive.
+	pub path: PathBuf,
+	/// The target of the symbolic link.
+	pub link: PathBuf,
+}
+
+impl Manifest {
+	/// Builds a manifest describing every entry in the given [`Header`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{manifest::Manifest, Header};
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let (header, _) = Header::read(&mut &asar_file[..])?;
+	/// let manifest = Manifest::from_header(&header);
+	/// println!("There are {} files in archive.asar", manifest.files.len());
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn from_header(header: &Header) -> Self {
+		let mut manifest = Self::default();
+		recursive_add(&mut manifest, Path::new(""), header);
+		manifest.files.sort_by(|a, b| a.path.cmp(&b.path));
+		manifest.symlinks.sort_by(|a, b| a.path.cmp(&b.path));
+		manifest
+	}
+}
+
+fn recursive_add(manifest: &mut Manifest, path: &Path, header: &Header) {
+	match header {
+		Header::File(file) => manifest.files.push(ManifestFile {
+			path: path.to_path_buf(),
+			size: file.size(),
+			offset: file.offset(),
+			executable: file.executable(),
+			unpacked: file.unpacked(),
+			algorithm: file.integrity().map(|integrity| integrity.algorithm()),
+			hash: file.integrity().map(|integrity| integrity.hash().to_vec()),
+		}),
+		Header::Directory { files } => {
+			for (name, header) in files {
+				recursive_add(manifest, &path.join(name), header);
+			}
+		}
+		Header::Link { link } => manifest.symlinks.push(ManifestSymlink {
+			path: path.to_path_buf(),
+			link: link.clone(),
+		}),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{header::TEST_ASAR, reader::AsarReader};
+	use std::path::Path;
+
+	#[test]
+	pub fn test_manifest() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let manifest = reader.manifest();
+		assert_eq!(manifest.files.len(), reader.files().len());
+		assert_eq!(manifest.symlinks.len(), reader.symlinks().len());
+
+		let file = &manifest.files[2];
+		assert_eq!(file.path, Path::new("folder 1/file 3.txt"));
+		assert_eq!(file.size, 3);
+		assert_eq!(file.offset, Some(26));
+		assert!(!file.executable);
+		assert!(!file.unpacked);
+		assert_eq!(
+			hex::encode(file.hash.as_ref().unwrap()),
+			"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+		);
+
+		let symlink = &manifest.symlinks[0];
+		assert_eq!(symlink.path, Path::new("symlink 1"));
+		assert_eq!(symlink.link, Path::new("folder 1/file 3.txt"));
+	}
+}
@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// Mapping a file into memory can't be done without `unsafe`, so this is the
+// only module where it's allowed.
+#![allow(unsafe_code)]
+
+use crate::{error::Result, reader::AsarReader};
+use std::{fs, io, os::unix::io::AsRawFd, path::Path, ptr, slice};
+
+/// A read-only memory map of a whole file, which is unmapped when it's
+/// dropped.
+#[derive(Debug)]
+struct Mmap {
+	ptr: *mut libc::c_void,
+	len: usize,
+}
+
+// The mapping is read-only and private, so it can be read from any thread.
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+	/// Maps the whole file at `path` into memory.
+	///
+	/// ## Safety
+	///
+	/// The file mustn't be modified or truncated while it's mapped.
+	unsafe fn open(path: &Path) -> io::Result<Self> {
+		let file = fs::File::open(path)?;
+		let len = usize::try_from(file.metadata()?.len()).map_err(io::Error::other)?;
+		// Empty mappings aren't allowed, but there's nothing to map anyway.
+		if len == 0 {
+			return Ok(Self {
+				ptr: ptr::null_mut(),
+				len,
+			});
+		}
+		let ptr = libc::mmap(
+			ptr::null_mut(),
+			len,
+			libc::PROT_READ,
+			libc::MAP_PRIVATE,
+			file.as_raw_fd(),
+			0,
+		);
+		if ptr == libc::MAP_FAILED {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(Self { ptr, len })
+	}
+
+	/// The contents of the file, which live for as long as the returned
+	/// lifetime is chosen to. It must not outlive the map.
+	const unsafe fn bytes<'a>(&self) -> &'a [u8] {
+		match self.len {
+			0 => &[],
+			len => slice::from_raw_parts(self.ptr.cast(), len),
+		}
+	}
+}
+
+impl Drop for Mmap {
+	fn drop(&mut self) {
+		if self.len != 0 {
+			// SAFETY: the pointer and length are those of a mapping made in
+			// `Mmap::open`, which nothing borrows from any more.
+			unsafe {
+				libc::munmap(self.ptr, self.len);
+			}
+		}
+	}
+}
+
+/// An [`AsarReader`] over a memory map of a file, as returned by
+/// [`AsarReader::open_mmap_at`], which unmaps the file when it's dropped.
+///
+/// Use [`MmapAsarReader::reader`] to read the archive.
+#[derive(Debug)]
+pub struct MmapAsarReader {
+	// This is declared before the map, so that it's dropped before the memory
+	// it borrows from is unmapped. It's only ever handed out with a lifetime
+	// tied to `self`.
+	reader: AsarReader<'static>,
+	_map: Mmap,
+}
+
+impl MmapAsarReader {
+	/// The [`AsarReader`] for the archive, whose files borrow from the memory
+	/// map.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarReader;
+	///
+	/// // SAFETY: app.exe isn't modified while it's mapped.
+	/// let mapped = unsafe { AsarReader::open_mmap_at("app.exe", 0x1a2b3c)? };
+	/// println!(
+	/// 	"There are {} files in the archive",
+	/// 	mapped.reader().files().len()
+	/// );
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub const fn reader(&self) -> &AsarReader<'_> {
+		&self.reader
+	}
+}
+
+impl AsarReader<'static> {
+	/// Maps the file at `path` into memory, and reads the asar archive which
+	/// starts `start_offset` bytes into it, such as an archive embedded in an
+	/// executable.
+	///
+	/// Only the parts of the file which are read are loaded into memory, so
+	/// this avoids reading a large executable to read a small file in the
+	/// archive. As there's no archive path to find a `.asar.unpacked`
+	/// directory next to, unpacked files are read as empty, like
+	/// [`AsarReader::new_at`] without an `asar_path`.
+	///
+	/// ## Safety
+	///
+	/// The file mustn't be modified or truncated while the returned
+	/// [`MmapAsarReader`] is alive, including by other processes, as that would
+	/// change, or unmap, memory that the reader borrows from.
+	///
+	/// ## Errors
+	///
+	///  - If the file can't be opened or mapped, returns an
+	///    [`Error::Io`](crate::Error::Io)
+	///  - If the file ends before the header does, including if `start_offset`
+	///    is past the end of the file, returns an
+	///    [`Error::HeaderTruncated`](crate::Error::HeaderTruncated)
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarReader;
+	/// use std::path::Path;
+	///
+	/// // SAFETY: app.exe isn't modified while it's mapped.
+	/// let mapped = unsafe { AsarReader::open_mmap_at("app.exe", 0x1a2b3c)? };
+	/// if let Some(config) = mapped.reader().read(Path::new("config.json")) {
+	/// 	println!("{}", String::from_utf8_lossy(config.data()));
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub unsafe fn open_mmap_at(
+		path: impl AsRef<Path>,
+		start_offset: usize,
+	) -> Result<MmapAsarReader> {
+		let map = Mmap::open(path.as_ref())?;
+		// The reader is stored next to the map, and dropped before it.
+		let reader = AsarReader::new_at(map.bytes(), start_offset, None)?;
+		Ok(MmapAsarReader { reader, _map: map })
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{error::Error, header::TEST_ASAR, reader::AsarReader, test_util::temp_path};
+	use std::fs;
+
+	#[test]
+	fn test_open_mmap_at() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let path = temp_path("open-mmap-at.exe");
+		let mut fused = b"not an asar".repeat(100);
+		let start_offset = fused.len();
+		fused.extend_from_slice(TEST_ASAR);
+		fs::write(&path, &fused).expect("failed to write file");
+
+		let mapped =
+			unsafe { AsarReader::open_mmap_at(&path, start_offset) }.expect("failed to map asar");
+		assert_eq!(mapped.reader().files(), reader.files());
+		assert_eq!(mapped.reader().symlinks(), reader.symlinks());
+		assert!(matches!(
+			unsafe { AsarReader::open_mmap_at(&path, fused.len() + 1) },
+			Err(Error::HeaderTruncated)
+		));
+		drop(mapped);
+		fs::remove_file(&path).expect("failed to clean up");
+	}
+}
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use crate::error::Result;
+use std::path::{Path, PathBuf};
+
+/// An entry in an ordering file, as used by `asar pack --ordering`.
+///
+/// Files listed in an ordering file are placed at the start of the archive's
+/// data, in the order they're listed, which can improve startup locality.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OrderingEntry {
+	/// The path of the file, relative to the directory being packed.
+	pub path: PathBuf,
+	/// Anything written before the last `:` on the line, if any.
+	///
+	/// Ordering files generated by tracing tools prefix each path with extra
+	/// details (such as a process ID), which are ignored when ordering, but
+	/// kept here for anything that wants to annotate entries.
+	pub annotation: Option<String>,
+}
+
+/// Reads and parses an ordering file.
+///
+/// See [`parse_ordering_str`] for details on the format.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use asar::ordering::parse_ordering;
+///
+/// for entry in parse_ordering("ordering.txt")? {
+/// 	println!("{}", entry.path.display());
+/// }
+/// # Ok::<(), asar::Error>(())
+/// ```
+pub fn parse_ordering(path: impl AsRef<Path>) -> Result<Vec<OrderingEntry>> {
+	let contents = std::fs::read_to_string(path)?;
+	Ok(parse_ordering_str(&contents))
+}
+
+/// Parses the contents of an ordering file.
+///
+/// Each line is a path, optionally prefixed by an annotation and a `:`, which
+/// matches the format accepted by `@electron/asar`. A leading `/` is stripped
+/// from paths, and blank lines are ignored.
+///
+/// ## Example
+///
+/// ```rust
+/// use asar::ordering::parse_ordering_str;
+/// use std::path::Path;
+///
+/// let entries = parse_ordering_str("index.js\n1234: /lib/main.js\n\n");
+/// assert_eq!(entries.len(), 2);
+/// assert_eq!(entries[1].path, Path::new("lib/main.js"));
+/// assert_eq!(entries[1].annotation.as_deref(), Some("1234"));
+/// ```
+pub fn parse_ordering_str(contents: &str) -> Vec<OrderingEntry> {
+	contents
+		.lines()
+		.filter_map(|line| {
+			let (annotation, path) = match line.rsplit_once(':') {
+				Some((annotation, path)) => (Some(annotation.trim().to_string()), path),
+				None => (None, line),
+			};
+			let path = path.trim();
+			let path = path.strip_prefix('/').unwrap_or(path);
+			if path.is_empty() {
+				return None;
+			}
+			Some(OrderingEntry {
+				path: PathBuf::from(path),
+				annotation,
+			})
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::{parse_ordering_str, OrderingEntry};
+
+	#[test]
+	fn test_parse_ordering() {
+		let entries = parse_ordering_str(
+			"file 1.txt\n/folder 1/file 3.txt\r\n  \n42: folder 1/folder 2/file 4.txt\n:\n",
+		);
+		assert_eq!(entries, vec![
+			OrderingEntry {
+				path: "file 1.txt".into(),
+				annotation: None,
+			},
+			OrderingEntry {
+				path: "folder 1/file 3.txt".into(),
+				annotation: None,
+			},
+			OrderingEntry {
+				path: "folder 1/folder 2/file 4.txt".into(),
+				annotation: Some("42".into()),
+			},
+		]);
+	}
+}
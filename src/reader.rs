@@ -1,12 +1,20 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 use crate::{
-	error::{Error, Result},
-	header::{FileIntegrity, FileLocation, Header},
+	error::{Error, PartialError, Result},
+	extract::normalize_path,
+	header::{FileIntegrity, FileLocation, HashAlgorithm, Header, DEFAULT_BLOCK_SIZE},
+	index::ArchiveIndex,
+	manifest::Manifest,
 };
+use serde::{Deserialize, Serialize};
+use serde_with::{hex::Hex, serde_as};
 use std::{
 	borrow::Cow,
-	collections::BTreeMap,
-	path::{Path, PathBuf},
+	cmp::Reverse,
+	collections::{BTreeMap, BinaryHeap},
+	fmt::{self, Display},
+	path::{Component, Path, PathBuf},
+	time::SystemTime,
 };
 
 /// An AsarReader is a struct that takes an asar [`Header`] and its offset,
@@ -35,10 +43,11 @@ use std::{
 /// ```
 #[derive(Debug, Clone, PartialEq)]
 pub struct AsarReader<'a> {
-	header: Header,
+	header: Cow<'a, Header>,
 	directories: BTreeMap<PathBuf, Vec<PathBuf>>,
 	files: BTreeMap<PathBuf, AsarFile<'a>>,
 	symlinks: BTreeMap<PathBuf, PathBuf>,
+	offsets: BTreeMap<usize, PathBuf>,
 	asar_path: Option<PathBuf>,
 }
 
@@ -63,12 +72,204 @@ impl<'a> AsarReader<'a> {
 		Self::new_from_header(header, offset, data, asar_path)
 	}
 
+	/// Parse and read an asar archive from a byte buffer, choosing what
+	/// happens to unpacked files which are missing from the `.asar.unpacked`
+	/// directory next to the archive.
+	///
+	/// [`AsarReader::new`] behaves like [`UnpackedPolicy::Fail`].
+	///
+	/// ## Errors
+	///
+	///  - If `policy` is [`UnpackedPolicy::Fail`] and an unpacked file is
+	///    missing, or if reading an unpacked file fails for any other reason,
+	///    returns an [`Error::UnpackedIoError`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{reader::UnpackedPolicy, AsarReader};
+	/// use std::{fs, path::PathBuf};
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let asar = AsarReader::new_with_unpacked_policy(
+	/// 	&asar_file,
+	/// 	PathBuf::from("archive.asar"),
+	/// 	UnpackedPolicy::SkipMissing,
+	/// )?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn new_with_unpacked_policy(
+		data: &'a [u8],
+		asar_path: impl Into<Option<PathBuf>>,
+		policy: UnpackedPolicy,
+	) -> Result<Self> {
+		let (header, offset) = Header::read(&mut &data[..])?;
+		Self::read_header(Cow::Owned(header), offset, data, asar_path.into(), policy)
+	}
+
+	/// Parse and read an asar archive from a byte buffer, failing if it
+	/// exceeds any of the given [`ReaderLimits`], which is useful for reading
+	/// untrusted archives safely.
+	///
+	/// The limits are checked before anything they limit is read.
+	///
+	/// ## Errors
+	///
+	///  - If the header is larger than [`ReaderLimits::max_header_size`],
+	///    returns an [`Error::HeaderTooLarge`]
+	///  - If the archive has more entries than [`ReaderLimits::max_entries`],
+	///    returns an [`Error::TooManyEntries`]
+	///  - If a file is larger than [`ReaderLimits::max_file_size`], returns an
+	///    [`Error::FileTooLarge`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{reader::ReaderLimits, AsarReader};
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("untrusted.asar")?;
+	/// let asar = AsarReader::new_with_limits(&asar_file, None, ReaderLimits {
+	/// 	max_header_size: Some(16 * 1024 * 1024),
+	/// 	max_entries: Some(100_000),
+	/// 	max_file_size: Some(256 * 1024 * 1024),
+	/// })?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn new_with_limits(
+		data: &'a [u8],
+		asar_path: impl Into<Option<PathBuf>>,
+		limits: ReaderLimits,
+	) -> Result<Self> {
+		let (header, offset) = Header::read_limited(&mut &data[..], limits.max_header_size)?;
+		limits.check(&header)?;
+		Self::read_header(
+			Cow::Owned(header),
+			offset,
+			data,
+			asar_path.into(),
+			UnpackedPolicy::Fail,
+		)
+	}
+
+	/// Parse and read an asar archive which starts `start_offset` bytes into a
+	/// byte buffer, such as an archive embedded in an executable.
+	///
+	/// All files borrow from `data`, so for large executables, `data` can be a
+	/// memory map of the whole file, which avoids reading it all into memory.
+	/// With the `mmap` feature, `AsarReader::open_mmap_at` maps the file
+	/// itself, but any crate which derefs its maps to `&[u8]` (such as
+	/// `memmap2`) will work too.
+	///
+	/// ## Errors
+	///
+	///  - If `data` ends before the header does, including if `start_offset` is
+	///    past the end of `data`, returns an [`Error::HeaderTruncated`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarReader;
+	/// use std::fs;
+	///
+	/// let executable = fs::read("app.exe")?;
+	/// let asar = AsarReader::new_at(&executable, 0x1a2b3c, None)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn new_at(
+		data: &'a [u8],
+		start_offset: usize,
+		asar_path: impl Into<Option<PathBuf>>,
+	) -> Result<Self> {
+		let data = data.get(start_offset..).ok_or(Error::HeaderTruncated)?;
+		Self::new(data, asar_path)
+	}
+
+	/// Parse and read an asar archive from a byte buffer which may only hold
+	/// the start of the archive, such as while it's being downloaded.
+	///
+	/// If `data` doesn't hold enough of the archive, this returns a
+	/// [`PartialError::NeedMore`] with the length `data` needs to reach to
+	/// get further: first to read the header, then to read each file, in the
+	/// order they're stored in. Unpacked files are ignored.
+	///
+	/// ## Errors
+	///
+	///  - If more of the archive is needed, returns a
+	///    [`PartialError::NeedMore`]
+	///  - If the archive is invalid, returns the [`Error`] that
+	///    [`AsarReader::new`] would, as a [`PartialError::Error`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{error::PartialError, AsarReader};
+	///
+	/// # let downloaded: Vec<u8> = Vec::new();
+	/// match AsarReader::try_new(&downloaded) {
+	/// 	Ok(asar) => println!("There are {} files in the asar", asar.files().len()),
+	/// 	Err(PartialError::NeedMore { required_len }) => {
+	/// 		println!("Need {} more bytes", required_len - downloaded.len())
+	/// 	}
+	/// 	Err(PartialError::Error(err)) => return Err(err),
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn try_new(data: &'a [u8]) -> std::result::Result<Self, PartialError> {
+		let need_more = |required_len| Err(PartialError::NeedMore { required_len });
+		let Some(json_size) = data.get(12..16) else {
+			return need_more(16);
+		};
+		let json_size = u32::from_le_bytes(json_size.try_into().expect("slice is 4 bytes"));
+		let header_len = 16 + json_size as usize;
+		if data.len() < header_len {
+			return need_more(header_len);
+		}
+		let (header, offset) = Header::read(&mut &data[..])?;
+		let next_end = Manifest::from_header(&header)
+			.files
+			.iter()
+			.filter_map(|file| offset.checked_add(file.offset?)?.checked_add(file.size))
+			.filter(|end| *end > data.len())
+			.min();
+		if let Some(required_len) = next_end {
+			return need_more(required_len);
+		}
+		Ok(Self::new_from_header(header, offset, data, None)?)
+	}
+
+	/// Parse and read an asar archive from a byte buffer, ignoring unpacked
+	/// files.
+	///
+	/// This is the same as calling [`AsarReader::new`] without an `asar_path`.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarReader;
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let asar = AsarReader::from_bytes(&asar_file)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn from_bytes(data: &'a [u8]) -> Result<Self> {
+		Self::new(data, None)
+	}
+
 	/// Read an asar archive from a byte buffer, using the given header and
 	/// offset.
 	///
 	/// If you care about unpacked files, pass a `asar_path` containing the path
 	/// to the asar archive.
 	///
+	/// ## Errors
+	///
+	///  - If the root of the header is a file or a symbolic link rather than a
+	///    directory, returns an [`Error::InvalidRoot`]
+	///  - If a file's integrity details don't have one block hash per block of
+	///    the file, returns an [`Error::InvalidIntegrity`]
+	///
 	/// ## Example
 	///
 	/// ```rust,no_run
@@ -85,11 +286,65 @@ impl<'a> AsarReader<'a> {
 		offset: usize,
 		data: &'a [u8],
 		asar_path: impl Into<Option<PathBuf>>,
+	) -> Result<Self> {
+		Self::read_header(
+			Cow::Owned(header),
+			offset,
+			data,
+			asar_path.into(),
+			UnpackedPolicy::Fail,
+		)
+	}
+
+	/// Parse and read an asar archive from a byte buffer like
+	/// [`AsarReader::new_from_header`], borrowing a header that has already
+	/// been parsed, instead of taking ownership of it.
+	///
+	/// The reader keeps the borrow, so the header is never cloned, and can
+	/// still be used separately once the reader is built.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarReader::new_from_header`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{AsarReader, Header};
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let (header, offset) = Header::read(&mut &asar_file[..])?;
+	/// let asar = AsarReader::new_from_header_ref(&header, offset, &asar_file, None)?;
+	/// println!("{} files", header.count().files);
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn new_from_header_ref(
+		header: &'a Header,
+		offset: usize,
+		data: &'a [u8],
+		asar_path: impl Into<Option<PathBuf>>,
+	) -> Result<Self> {
+		Self::read_header(
+			Cow::Borrowed(header),
+			offset,
+			data,
+			asar_path.into(),
+			UnpackedPolicy::Fail,
+		)
+	}
+
+	fn read_header(
+		header: Cow<'a, Header>,
+		offset: usize,
+		data: &'a [u8],
+		asar_path: Option<PathBuf>,
+		unpacked_policy: UnpackedPolicy,
 	) -> Result<Self> {
 		let mut files = BTreeMap::new();
 		let mut directories = BTreeMap::new();
 		let mut symlinks = BTreeMap::new();
-		let asar_path = asar_path.into();
+		header.check_root()?;
 		recursive_read(
 			PathBuf::new(),
 			&mut files,
@@ -99,16 +354,54 @@ impl<'a> AsarReader<'a> {
 			offset,
 			data,
 			asar_path.as_deref(),
+			unpacked_policy,
 		)?;
+		// Empty files take up no space, so they can share an offset with the
+		// file after them, which is the one that's really at that offset.
+		let mut offsets = BTreeMap::new();
+		let (empty, non_empty) = files
+			.iter()
+			.filter_map(|(path, file)| Some((file.offset()?, path, file)))
+			.partition::<Vec<_>, _>(|(_, _, file)| file.data().is_empty());
+		for (file_offset, path, _) in non_empty.into_iter().chain(empty) {
+			offsets.entry(file_offset).or_insert_with(|| path.clone());
+		}
 		Ok(Self {
 			header,
 			files,
 			directories,
 			symlinks,
+			offsets,
 			asar_path,
 		})
 	}
 
+	/// The [`Header`] of the asar.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let counts = asar.header().count();
+	/// println!(
+	/// 	"There are {} directories in archive.asar",
+	/// 	counts.directories
+	/// );
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub const fn header(&self) -> &Header {
+		// Dereferencing a `Cow` isn't `const`, but matching on it is.
+		match &self.header {
+			Cow::Borrowed(header) => header,
+			Cow::Owned(header) => header,
+		}
+	}
+
 	/// Gets all files in the asar.
 	///
 	/// ## Example
@@ -134,7 +427,9 @@ impl<'a> AsarReader<'a> {
 		&self.files
 	}
 
-	/// Gets all directories in the asar.
+	/// Gets all directories in the asar, including empty ones.
+	///
+	/// The root directory is always listed, under an empty path.
 	///
 	/// ## Example
 	///
@@ -178,8 +473,114 @@ impl<'a> AsarReader<'a> {
 		&self.symlinks
 	}
 
+	/// How deeply nested the deepest entry in the archive is, counting each
+	/// component of its path, so an entry at the root has a depth of 1. An
+	/// empty archive has a depth of 0.
+	///
+	/// Every file, directory, and symbolic link counts. A very deep archive,
+	/// such as one with deeply nested `node_modules`, can be slow to walk, and
+	/// often hints at a packaging problem.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// if asar.max_depth() > 32 {
+	/// 	println!("{} is very deep", asar.deepest_path().unwrap().display());
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn max_depth(&self) -> usize {
+		self.deepest_path()
+			.map_or(0, |path| path.components().count())
+	}
+
+	/// The path of the deepest entry in the archive, as measured by
+	/// [`AsarReader::max_depth`], or `None` if the archive is empty.
+	///
+	/// If several entries are as deep, the first of them by path is returned.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// if let Some(path) = asar.deepest_path() {
+	/// 	println!("the deepest entry is {}", path.display());
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn deepest_path(&self) -> Option<&Path> {
+		self.files
+			.keys()
+			.chain(self.directories.keys())
+			.chain(self.symlinks.keys())
+			.map(PathBuf::as_path)
+			.filter(|path| !path.as_os_str().is_empty())
+			.map(|path| (path.components().count(), Reverse(path)))
+			.max()
+			.map(|(_, Reverse(path))| path)
+	}
+
+	/// The path of the asar archive, if one was given when reading it.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarReader;
+	/// use std::{fs, path::PathBuf};
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let asar = AsarReader::new(&asar_file, PathBuf::from("archive.asar"))?;
+	/// assert_eq!(
+	/// 	asar.asar_path(),
+	/// 	Some(PathBuf::from("archive.asar").as_path())
+	/// );
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub fn asar_path(&self) -> Option<&Path> {
+		self.asar_path.as_deref()
+	}
+
+	/// The directory that unpacked files are stored in, next to the asar
+	/// archive, if its path was given when reading it.
+	///
+	/// For `app.asar`, this is `app.asar.unpacked`.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarReader;
+	/// use std::{fs, path::PathBuf};
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let asar = AsarReader::new(&asar_file, PathBuf::from("archive.asar"))?;
+	/// assert_eq!(
+	/// 	asar.unpacked_dir(),
+	/// 	Some(PathBuf::from("archive.asar.unpacked"))
+	/// );
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub fn unpacked_dir(&self) -> Option<PathBuf> {
+		self.asar_path
+			.as_deref()
+			.map(|asar_path| asar_path.with_extension("asar.unpacked"))
+	}
+
 	/// Gets information about a file.
 	///
+	/// Symbolic links are followed, using [`AsarReader::resolve`]. This is
+	/// the same as [`AsarReader::get_or_resolve`].
+	///
 	/// ## Example
 	///
 	/// ```rust,no_run
@@ -194,14 +595,17 @@ impl<'a> AsarReader<'a> {
 	/// # Ok::<(), asar::Error>(())
 	/// ```
 	#[inline]
-	pub fn read(&self, path: &Path) -> Option<&AsarFile> {
-		if let Some(link) = self.symlinks.get(path) {
-			return self.files.get(link);
-		}
-		self.files.get(path)
+	pub fn read(&self, path: &Path) -> Option<&AsarFile<'a>> {
+		self.files.get(&self.resolve(path).ok()?)
 	}
 
-	/// Gets the contents of a directory.
+	/// Gets the source map for a JavaScript file, which is the file next to
+	/// it with `.map` appended to its name, such as `main.js.map` for
+	/// `main.js`, or `None` if there isn't one.
+	///
+	/// Symbolic links are followed, like with [`AsarReader::read`]. Source
+	/// maps which are only referenced by a `sourceMappingURL` comment, or
+	/// inlined into the file, aren't found.
 	///
 	/// ## Example
 	///
@@ -212,30 +616,60 @@ impl<'a> AsarReader<'a> {
 	///
 	/// # let asar_file = fs::read("archive.asar")?;
 	/// # let asar = AsarReader::new(&asar_file, None)?;
-	/// let contents = asar.read_dir(Path::new("dir a/dir b")).unwrap();
-	/// for file in contents {
-	/// 	println!("file {}", file.display());
+	/// if let Some(map) = asar.source_map_for(Path::new("dist/main.js")) {
+	/// 	println!("main.js has a {} byte source map", map.data().len());
 	/// }
 	/// # Ok::<(), asar::Error>(())
 	/// ```
-	#[inline]
-	pub fn read_dir(&self, path: &Path) -> Option<&[PathBuf]> {
-		self.directories.get(path).map(|paths| paths.as_slice())
+	pub fn source_map_for(&self, js_path: &Path) -> Option<&AsarFile<'a>> {
+		let mut map_path = js_path.as_os_str().to_owned();
+		map_path.push(".map");
+		self.read(Path::new(&map_path))
 	}
-}
 
-/// This represents a file in an asar archive, with a byte slice referencing the
-/// contents, and the integrity details containing file hashes.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct AsarFile<'a> {
-	data: Cow<'a, [u8]>,
-	integrity: Option<FileIntegrity>,
-}
+	/// Gets the Electron integrity recorded in the archive's `package.json`,
+	/// or `None` if there's no `package.json` at the root, or it has no valid
+	/// `integrity` field.
+	///
+	/// Electron itself doesn't read this: it checks `app.asar` against the
+	/// `ElectronAsarIntegrity` key of the app's `Info.plist` on macOS, or an
+	/// `Integrity` resource of the executable on Windows, which are outside
+	/// of the archive. Some packagers copy the same `algorithm` and `hash`
+	/// into `package.json`, which is the only place this looks. To check an
+	/// archive against an integrity, compare it to
+	/// `ElectronIntegrity::of_header`, with the `integrity` feature.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{reader::ElectronIntegrity, AsarReader, Header};
+	///
+	/// # let asar_file = fs::read("app.asar")?;
+	/// let asar = AsarReader::new(&asar_file, None)?;
+	/// let (header, _) = Header::read_raw(&mut &asar_file[..])?;
+	/// if let Some(integrity) = asar.electron_integrity() {
+	/// 	assert_eq!(integrity, ElectronIntegrity::of_header(&header));
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn electron_integrity(&self) -> Option<ElectronIntegrity> {
+		let package_json = self.read(Path::new("package.json"))?;
+		let mut package = serde_json::from_slice::<serde_json::Value>(package_json.data()).ok()?;
+		serde_json::from_value(package.get_mut("integrity")?.take()).ok()
+	}
 
-impl<'a> AsarFile<'a> {
-	/// The data of the file.
+	/// Gets information about a file like [`AsarReader::read`], matching paths
+	/// which only differ in their Unicode normalization.
+	///
+	/// macOS stores file names decomposed (NFD), while most other systems store
+	/// them composed (NFC), so an archive packed on one may store a path with
+	/// different bytes than the one looked up on another. The path is looked
+	/// up as given first, then normalized to NFC, and then compared against
+	/// the normalized path of every file, which is slower for large archives.
 	///
 	/// ## Example
+	///
 	/// ```rust,no_run
 	/// # use std::fs;
 	/// use asar::AsarReader;
@@ -243,18 +677,38 @@ impl<'a> AsarFile<'a> {
 	///
 	/// # let asar_file = fs::read("archive.asar")?;
 	/// # let asar = AsarReader::new(&asar_file, None)?;
-	/// let file_info = asar.read(Path::new("hello.txt")).unwrap();
-	/// assert_eq!(file_info.data(), b"Hello, World!");
+	/// // "résumé.txt", decomposed
+	/// let file_info = asar.read_normalized(Path::new("re\u{301}sume\u{301}.txt"));
 	/// # Ok::<(), asar::Error>(())
 	/// ```
-	#[inline]
-	pub fn data(&self) -> &[u8] {
-		self.data.as_ref()
+	#[cfg(feature = "normalize-unicode")]
+	pub fn read_normalized(&self, path: &Path) -> Option<&AsarFile<'a>> {
+		use crate::unicode::nfc_path;
+
+		if let Some(file) = self.read(path) {
+			return Some(file);
+		}
+		let path = self.resolve(&nfc_path(path)).ok()?;
+		self.files.get(&path).or_else(|| {
+			self.files
+				.iter()
+				.find(|(candidate, _)| nfc_path(candidate) == path.as_path())
+				.map(|(_, file)| file)
+		})
 	}
 
-	/// Integrity details of the file, such as hashes.
+	/// Gets a file, following symbolic links to it.
+	///
+	/// If `path` is a file, it's returned directly. Otherwise, if `path` (or
+	/// one of its parent directories) is a symbolic link, it's resolved with
+	/// [`AsarReader::resolve`], and the file it points to is returned. If
+	/// there's no file at either, or the links form a loop, this returns
+	/// `None`.
+	///
+	/// This is the same as [`AsarReader::read`].
 	///
 	/// ## Example
+	///
 	/// ```rust,no_run
 	/// # use std::fs;
 	/// use asar::AsarReader;
@@ -262,99 +716,1079 @@ impl<'a> AsarFile<'a> {
 	///
 	/// # let asar_file = fs::read("archive.asar")?;
 	/// # let asar = AsarReader::new(&asar_file, None)?;
-	/// let file_info = asar.read(Path::new("hello.txt")).unwrap();
-	/// let integrity = file_info.integrity().unwrap();
-	/// assert_eq!(
-	/// 	integrity.hash(),
-	/// 	b"\xf6\x95\x2d\x6e\xef\x55\x5d\xdd\x87\xac\xa6\x6e\x56\xb9\x15\x30\x22\
-	/// x2d\x6e\x31\x84\x14\x81\x6f\x3b\xa7\xcf\x5b\xf6\x94\xbf\x0f"
-	/// );
+	/// let file = asar.get_or_resolve(Path::new("link to hello.txt")).unwrap();
+	/// assert_eq!(file.data(), b"Hello, World!");
 	/// # Ok::<(), asar::Error>(())
 	/// ```
 	#[inline]
-	pub const fn integrity(&self) -> Option<&FileIntegrity> {
-		self.integrity.as_ref()
+	pub fn get_or_resolve(&self, path: &Path) -> Option<&AsarFile<'a>> {
+		self.read(path)
 	}
-}
 
-fn recursive_read<'a>(
-	path: PathBuf,
-	file_map: &mut BTreeMap<PathBuf, AsarFile<'a>>,
-	dir_map: &mut BTreeMap<PathBuf, Vec<PathBuf>>,
-	symlink_map: &mut BTreeMap<PathBuf, PathBuf>,
-	header: &Header,
-	begin_offset: usize,
-	data: &'a [u8],
+	/// Finds the V8 snapshot embedded at the root of the asar, if there is
+	/// one, checking each of [`SNAPSHOT_FILE_NAMES`] in order.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// if let Some(snapshot) = asar.snapshot() {
+	/// 	println!("V8 snapshot is {} bytes", snapshot.data().len());
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn snapshot(&self) -> Option<&AsarFile<'a>> {
+		SNAPSHOT_FILE_NAMES
+			.iter()
+			.find_map(|name| self.files.get(Path::new(name)))
+	}
+
+	/// Gets the exact bytes of a file in the original archive buffer.
+	///
+	/// Unlike [`AsarFile::data`], this borrows from the buffer the asar was
+	/// read from, so it can be written into another archive verbatim. Symbolic
+	/// links aren't followed.
+	///
+	/// Returns `None` if the file doesn't exist, or is unpacked.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let bytes = asar.bytes_for(Path::new("hello.txt")).unwrap();
+	/// assert_eq!(bytes, b"Hello, World!");
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn bytes_for(&self, path: &Path) -> Option<&'a [u8]> {
+		let file = self.files.get(path)?;
+		match (file.location, &file.data) {
+			(FileLocation::Offset { .. }, Cow::Borrowed(data)) => Some(data),
+			_ => None,
+		}
+	}
+
+	/// Resolves all the symbolic links in a path, including in its parent
+	/// directories, returning the path of the entry it refers to.
+	///
+	/// The returned path isn't guaranteed to exist in the asar.
+	///
+	/// ## Errors
+	///
+	///  - If more than [`MAX_SYMLINK_DEPTH`] symbolic links are followed, such
+	///    as when links point to each other, returns an [`Error::SymlinkLoop`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let target = asar.resolve(Path::new("link to hello.txt"))?;
+	/// assert_eq!(target, Path::new("hello.txt"));
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn resolve(&self, path: &Path) -> Result<PathBuf> {
+		let mut resolved = PathBuf::new();
+		let mut pending = path.components().rev().collect::<Vec<_>>();
+		let mut depth = 0;
+		while let Some(component) = pending.pop() {
+			match component {
+				Component::Normal(name) => resolved.push(name),
+				Component::ParentDir => {
+					resolved.pop();
+					continue;
+				}
+				_ => continue,
+			}
+			if let Some(link) = self.symlinks.get(&resolved) {
+				depth += 1;
+				if depth > MAX_SYMLINK_DEPTH {
+					return Err(Error::SymlinkLoop(path.to_path_buf()));
+				}
+				resolved.clear();
+				pending.extend(link.components().rev());
+			}
+		}
+		Ok(resolved)
+	}
+
+	/// Gets the target of a symbolic link, as a normalized path relative to
+	/// the root of the archive, or `None` if `path` isn't a symbolic link, or
+	/// its target climbs out of the root with `..`.
+	///
+	/// Links in an asar are relative to the root of the archive, not to the
+	/// directory containing the link, so the target only has its `.` and `..`
+	/// components resolved. Unlike [`AsarReader::resolve`], links in the
+	/// target aren't followed.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let target = asar.symlink_target(Path::new("link to hello.txt"));
+	/// assert_eq!(target.as_deref(), Some(Path::new("hello.txt")));
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn symlink_target(&self, path: &Path) -> Option<PathBuf> {
+		let link = self.symlinks.get(path)?;
+		// A leading `/` is ignored, as the link is relative to the root anyway.
+		let link = link
+			.components()
+			.filter(|component| !matches!(component, Component::RootDir | Component::Prefix(_)))
+			.collect::<PathBuf>();
+		let target = normalize_path(&link);
+		(!target.starts_with("..")).then_some(target)
+	}
+
+	/// Gets the contents of a directory.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let contents = asar.read_dir(Path::new("dir a/dir b")).unwrap();
+	/// for file in contents {
+	/// 	println!("file {}", file.display());
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub fn read_dir(&self, path: &Path) -> Option<&[PathBuf]> {
+		self.directories.get(path).map(|paths| paths.as_slice())
+	}
+
+	/// Gets the entries at the top level of the asar.
+	///
+	/// This is the same as calling [`AsarReader::read_dir`] with an empty
+	/// path, but is empty rather than `None` for an empty archive.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// for path in asar.root() {
+	/// 	println!("{}", path.display());
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub fn root(&self) -> &[PathBuf] {
+		self.read_dir(Path::new("")).unwrap_or_default()
+	}
+
+	/// Gets the contents of a directory, along with the kind of each entry, and
+	/// the size of each file.
+	///
+	/// This is like [`AsarReader::read_dir`], but saves having to look up each
+	/// entry afterwards.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{reader::EntryKind, AsarReader};
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// for entry in asar.read_dir_entries(Path::new("dir a")).unwrap() {
+	/// 	match entry.size {
+	/// 		Some(size) => println!("{} ({size} bytes)", entry.path.display()),
+	/// 		None => println!("{} ({})", entry.path.display(), entry.kind),
+	/// 	}
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn read_dir_entries(&self, path: &Path) -> Option<Vec<DirEntry>> {
+		let entries = self
+			.read_dir(path)?
+			.iter()
+			.filter_map(|path| {
+				let kind = self.entry_kind(path)?;
+				let size = self.files.get(path).map(|file| file.data().len());
+				Some(DirEntry {
+					path: path.clone(),
+					kind,
+					size,
+				})
+			})
+			.collect();
+		Some(entries)
+	}
+
+	/// Gets the kind of the entry at a path, or `None` if nothing exists at
+	/// that path.
+	///
+	/// If a path is somehow listed as more than one kind of entry, files take
+	/// precedence over directories, which take precedence over symbolic links.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{reader::EntryKind, AsarReader};
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// assert_eq!(
+	/// 	asar.entry_kind(Path::new("hello.txt")),
+	/// 	Some(EntryKind::File)
+	/// );
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn entry_kind(&self, path: &Path) -> Option<EntryKind> {
+		if self.files.contains_key(path) {
+			Some(EntryKind::File)
+		} else if self.directories.contains_key(path) {
+			Some(EntryKind::Directory)
+		} else if self.symlinks.contains_key(path) {
+			Some(EntryKind::Symlink)
+		} else {
+			None
+		}
+	}
+
+	/// Gets the `n` largest files in the asar, sorted by size in descending
+	/// order.
+	///
+	/// Files of the same size are sorted by path.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// for (path, size) in asar.largest_files(10) {
+	/// 	println!("{} is {} bytes", path.display(), size);
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn largest_files(&self, n: usize) -> Vec<(&Path, usize)> {
+		if n == 0 {
+			return Vec::new();
+		}
+		let mut heap = BinaryHeap::with_capacity(n + 1);
+		for (path, file) in &self.files {
+			heap.push(Reverse((file.data().len(), Reverse(path.as_path()))));
+			if heap.len() > n {
+				heap.pop();
+			}
+		}
+		heap.into_sorted_vec()
+			.into_iter()
+			.map(|Reverse((size, Reverse(path)))| (path, size))
+			.collect()
+	}
+
+	/// Finds the file stored at `offset` in the asar's data region.
+	///
+	/// Empty files can share an offset with the file after them, in which case
+	/// the non-empty file is returned. Otherwise, if several files share an
+	/// offset, the first by path is returned.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// if let Some((path, file)) = asar.file_at_offset(1024) {
+	/// 	println!(
+	/// 		"{} is at offset 1024, and is {} bytes",
+	/// 		path.display(),
+	/// 		file.data().len()
+	/// 	);
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn file_at_offset(&self, offset: usize) -> Option<(&Path, &AsarFile<'a>)> {
+		let path = self.offsets.get(&offset)?;
+		Some((path.as_path(), &self.files[path]))
+	}
+
+	/// Gets the files stored in the asar, sorted by their offset in its data
+	/// region, which is the order they were packed in.
+	///
+	/// Unpacked files aren't stored in the asar, so they're left out. Files at
+	/// the same offset, which can only happen for empty files, are sorted by
+	/// path.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// for (path, file) in asar.files_by_offset() {
+	/// 	println!("{} is at offset {}", path.display(), file.offset().unwrap());
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn files_by_offset(&self) -> Vec<(&Path, &AsarFile<'a>)> {
+		let mut files = self
+			.files
+			.iter()
+			.filter_map(|(path, file)| Some((file.offset()?, path.as_path(), file)))
+			.collect::<Vec<_>>();
+		// `files` is already sorted by path, so a stable sort keeps ties in order.
+		files.sort_by_key(|(offset, ..)| *offset);
+		files
+			.into_iter()
+			.map(|(_, path, file)| (path, file))
+			.collect()
+	}
+
+	/// Builds a flat [`Manifest`] describing every file and symbolic link in
+	/// the asar.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let manifest = asar.manifest();
+	/// for file in &manifest.files {
+	/// 	println!("{} is {} bytes", file.path.display(), file.size);
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn manifest(&self) -> Manifest {
+		Manifest::from_header(&self.header)
+	}
+
+	/// Builds an [`ArchiveIndex`] of the structure of the asar, which can be
+	/// serialized and queried later without the asar's contents.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// fs::write("archive.json", serde_json::to_vec(&asar.index())?)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn index(&self) -> ArchiveIndex {
+		ArchiveIndex::from_header(&self.header)
+			.expect("header was already checked while reading the asar")
+	}
+
+	/// Finds every cycle of symbolic links in the asar, such as `a -> b` and
+	/// `b -> a`.
+	///
+	/// Each cycle is listed once, as the paths of the symbolic links in the
+	/// order they point to each other, starting from the lowest path. Cycles
+	/// are sorted by their first path.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// for cycle in asar.detect_symlink_cycles() {
+	/// 	println!("symbolic link cycle: {cycle:?}");
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn detect_symlink_cycles(&self) -> Vec<Vec<PathBuf>> {
+		// Each symbolic link points to at most one other, so following the
+		// links from any start either ends, reaches a link visited from an
+		// earlier start, or loops back onto the current walk.
+		let mut visited = BTreeMap::<&Path, usize>::new();
+		let mut cycles = Vec::new();
+		for (walk, start) in self.symlinks.keys().enumerate() {
+			let mut current = start.as_path();
+			let mut chain = Vec::new();
+			while let Some(link) = self.symlinks.get(current) {
+				match visited.get(current) {
+					Some(&seen_in) if seen_in == walk => {
+						let begin = chain
+							.iter()
+							.position(|path| *path == current)
+							.expect("path visited in this walk should be in the chain");
+						let mut cycle = chain[begin..]
+							.iter()
+							.map(|path: &&Path| path.to_path_buf())
+							.collect::<Vec<_>>();
+						let lowest = cycle
+							.iter()
+							.enumerate()
+							.min_by_key(|(_, path)| *path)
+							.map_or(0, |(idx, _)| idx);
+						cycle.rotate_left(lowest);
+						cycles.push(cycle);
+						break;
+					}
+					Some(_) => break,
+					None => {
+						visited.insert(current, walk);
+						chain.push(current);
+						current = link;
+					}
+				}
+			}
+		}
+		cycles.sort();
+		cycles
+	}
+
+	/// Lazily checks the contents of every file in the asar against its
+	/// integrity details, one file per call to [`Iterator::next`], in path
+	/// order.
+	///
+	/// This makes it easy to report progress, or to stop early. Files without
+	/// integrity details are assumed to be valid.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let total = asar.files().len();
+	/// for (idx, (path, result)) in asar.verify_iter().enumerate() {
+	/// 	match result {
+	/// 		Ok(()) => println!("[{}/{total}] {} is valid", idx + 1, path.display()),
+	/// 		Err(err) => println!("[{}/{total}] {err}", idx + 1),
+	/// 	}
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[cfg(feature = "integrity")]
+	pub fn verify_iter(&self) -> impl Iterator<Item = (&Path, Result<()>)> + '_ {
+		self.files.iter().map(|(path, file)| {
+			let result = match file.integrity() {
+				Some(integrity) => crate::integrity::verify(path, file.stored_data(), integrity),
+				None => Ok(()),
+			};
+			(path.as_path(), result)
+		})
+	}
+
+	/// Checks the contents of a single file in the asar against its integrity
+	/// details, checking each block before the whole file.
+	///
+	/// This is useful to check a file right before it's used, without
+	/// verifying the rest of the archive. Files without integrity details are
+	/// assumed to be valid.
+	///
+	/// ## Errors
+	///
+	///  - If the file doesn't exist, returns an [`Error::FileNotFound`]
+	///  - If a block, or the whole file, doesn't match its hash, returns an
+	///    [`Error::HashMismatch`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::{fs, path::Path};
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// asar.verify_file(Path::new("main.js"))?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[cfg(feature = "integrity")]
+	pub fn verify_file(&self, path: &Path) -> Result<()> {
+		let file = self
+			.files
+			.get(path)
+			.ok_or_else(|| Error::FileNotFound(path.to_path_buf()))?;
+		match file.integrity() {
+			Some(integrity) => crate::integrity::verify(path, file.stored_data(), integrity),
+			None => Ok(()),
+		}
+	}
+
+	/// Computes a single digest over the logical contents of the asar: the
+	/// path and content hash of every file, and the path and target of every
+	/// symbolic link.
+	///
+	/// Two archives with the same contents produce the same checksum, even if
+	/// their files are laid out differently in the archive.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{AsarReader, HashAlgorithm};
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let checksum = asar.checksum(HashAlgorithm::Sha256);
+	/// println!("archive.asar checksum: {}", hex::encode(checksum));
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[cfg(feature = "integrity")]
+	pub fn checksum(&self, algorithm: HashAlgorithm) -> Vec<u8> {
+		let mut canonical = Vec::new();
+		for (path, file) in &self.files {
+			canonical.extend_from_slice(b"file\0");
+			canonical.extend_from_slice(path.to_string_lossy().as_bytes());
+			canonical.push(0);
+			canonical.extend_from_slice(&algorithm.hash(file.data()));
+		}
+		for (path, link) in &self.symlinks {
+			canonical.extend_from_slice(b"link\0");
+			canonical.extend_from_slice(path.to_string_lossy().as_bytes());
+			canonical.push(0);
+			canonical.extend_from_slice(link.to_string_lossy().as_bytes());
+			canonical.push(0);
+		}
+		algorithm.hash(&canonical)
+	}
+}
+
+/// The integrity Electron checks an asar archive against, as found by
+/// [`AsarReader::electron_integrity`].
+///
+/// This is a hash of the archive's header JSON, which in turn has the hashes
+/// of each file, so it covers the whole archive.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ElectronIntegrity {
+	/// The hashing algorithm used to calculate the hash.
+	pub algorithm: HashAlgorithm,
+	/// The hash of the header JSON, in hex format.
+	#[serde_as(as = "Hex")]
+	pub hash: Vec<u8>,
+}
+
+impl ElectronIntegrity {
+	/// Calculates the integrity Electron expects for an archive with the given
+	/// header JSON, as returned by [`Header::read_raw`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{reader::ElectronIntegrity, Header};
+	/// use std::fs;
+	///
+	/// let asar_file = fs::read("app.asar")?;
+	/// let (header, _) = Header::read_raw(&mut &asar_file[..])?;
+	/// let integrity = ElectronIntegrity::of_header(&header);
+	/// println!("{}", hex::encode(integrity.hash));
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[cfg(feature = "integrity")]
+	pub fn of_header(json: &[u8]) -> Self {
+		Self {
+			algorithm: HashAlgorithm::Sha256,
+			hash: HashAlgorithm::Sha256.hash(json),
+		}
+	}
+}
+
+/// The names of the files Electron stores V8 snapshots in, as found by
+/// [`AsarReader::snapshot`].
+pub const SNAPSHOT_FILE_NAMES: &[&str] = &["v8_context_snapshot.bin", "snapshot_blob.bin"];
+
+/// The maximum amount of symbolic links followed by [`AsarReader::resolve`]
+/// before giving up, which matches the limit used by Linux.
+pub const MAX_SYMLINK_DEPTH: usize = 40;
+
+/// The kind of an entry in an asar archive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EntryKind {
+	/// A file, with contents.
+	File,
+	/// A directory, containing other entries.
+	Directory,
+	/// A symbolic link to another entry.
+	Symlink,
+}
+
+/// An entry in a directory, as returned by [`AsarReader::read_dir_entries`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DirEntry {
+	/// The path of the entry, relative to the root of the archive.
+	pub path: PathBuf,
+	/// The kind of the entry.
+	pub kind: EntryKind,
+	/// The size of the entry in bytes, if it's a file.
+	pub size: Option<usize>,
+}
+
+impl Display for EntryKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::File => write!(f, "file"),
+			Self::Directory => write!(f, "directory"),
+			Self::Symlink => write!(f, "symbolic link"),
+		}
+	}
+}
+
+impl<'r, 'a> IntoIterator for &'r AsarReader<'a> {
+	type IntoIter = Iter<'r, 'a>;
+	type Item = (&'r Path, &'r AsarFile<'a>);
+
+	/// Iterates over the files in the asar, sorted by path, like
+	/// [`AsarReader::files`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// for (path, file) in &asar {
+	/// 	println!("{} is {} bytes", path.display(), file.data().len());
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	fn into_iter(self) -> Self::IntoIter {
+		Iter {
+			files: self.files.iter(),
+		}
+	}
+}
+
+/// An iterator over the files in an [`AsarReader`], sorted by path.
+///
+/// This is created by iterating over a `&AsarReader`.
+#[derive(Debug, Clone)]
+pub struct Iter<'r, 'a> {
+	files: std::collections::btree_map::Iter<'r, PathBuf, AsarFile<'a>>,
+}
+
+impl<'r, 'a> Iterator for Iter<'r, 'a> {
+	type Item = (&'r Path, &'r AsarFile<'a>);
+
+	#[inline]
+	fn next(&mut self) -> Option<Self::Item> {
+		self.files.next().map(|(path, file)| (path.as_path(), file))
+	}
+
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.files.size_hint()
+	}
+}
+
+impl DoubleEndedIterator for Iter<'_, '_> {
+	#[inline]
+	fn next_back(&mut self) -> Option<Self::Item> {
+		self.files
+			.next_back()
+			.map(|(path, file)| (path.as_path(), file))
+	}
+}
+
+impl ExactSizeIterator for Iter<'_, '_> {}
+
+/// Limits on the size of an archive, for reading untrusted archives with
+/// [`AsarReader::new_with_limits`] or
+/// [`StreamingAsarReader::new_with_limits`](crate::StreamingAsarReader::new_with_limits).
+///
+/// Each limit is off when it's `None`, which is the default.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ReaderLimits {
+	/// The maximum length of the header JSON, in bytes.
+	pub max_header_size: Option<usize>,
+	/// The maximum amount of files, directories, and symbolic links, not
+	/// counting the root directory.
+	pub max_entries: Option<usize>,
+	/// The maximum size of a single file, in bytes, both in the archive and
+	/// once it's decompressed.
+	pub max_file_size: Option<usize>,
+}
+
+impl ReaderLimits {
+	/// Checks the entries of a header against the limits. The size of the
+	/// header has to be checked while it's read.
+	pub(crate) fn check(&self, header: &Header) -> Result<()> {
+		if let Some(max) = self.max_entries {
+			let counts = header.count();
+			let count = counts.files + counts.directories + counts.symlinks;
+			if count > max {
+				return Err(Error::TooManyEntries { count, max });
+			}
+		}
+		if let Some(max) = self.max_file_size {
+			let mut stack = vec![(PathBuf::new(), header)];
+			while let Some((path, header)) = stack.pop() {
+				match header {
+					Header::File(file) => {
+						// Compressed files are limited by how large they are once
+						// they're decompressed, too.
+						let size = file
+							.compression()
+							.map_or(0, |compression| compression.original_size);
+						let size = size.max(file.size());
+						if size > max {
+							return Err(Error::FileTooLarge { path, size, max });
+						}
+					}
+					Header::Directory { files } => {
+						stack.extend(files.iter().map(|(name, header)| (path.join(name), header)));
+					}
+					_ => {}
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+/// What [`AsarReader::new_with_unpacked_policy`] does with an unpacked file
+/// which is missing from the `.asar.unpacked` directory next to the archive,
+/// such as when the directory doesn't exist at all.
+///
+/// Other errors reading unpacked files are always returned.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum UnpackedPolicy {
+	/// Fail with an [`Error::UnpackedIoError`].
+	#[default]
+	Fail,
+	/// Leave the file out of [`AsarReader::files`] and
+	/// [`AsarReader::directories`]. It's still listed in
+	/// [`AsarReader::header`].
+	SkipMissing,
+	/// Read the file as empty, as if no `asar_path` was given.
+	Empty,
+}
+
+/// This represents a file in an asar archive, with a byte slice referencing the
+/// contents, and the integrity details containing file hashes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AsarFile<'a> {
+	data: Cow<'a, [u8]>,
+	/// The contents as they're stored in the archive, if they're compressed,
+	/// and so differ from `data`.
+	stored: Option<Cow<'a, [u8]>>,
+	location: FileLocation,
+	integrity: Option<FileIntegrity>,
+	executable: bool,
+	mtime: Option<SystemTime>,
+}
+
+impl<'a> AsarFile<'a> {
+	/// The data of the file.
+	///
+	/// ## Example
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let file_info = asar.read(Path::new("hello.txt")).unwrap();
+	/// assert_eq!(file_info.data(), b"Hello, World!");
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub fn data(&self) -> &[u8] {
+		self.data.as_ref()
+	}
+
+	/// The contents of the file as they're stored in the archive, which are
+	/// what its integrity details are the hashes of.
+	///
+	/// This is the same as [`AsarFile::data`], unless the file is compressed,
+	/// in which case it's the compressed contents.
+	///
+	/// ## Example
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let file_info = asar.read(Path::new("hello.txt")).unwrap();
+	/// println!(
+	/// 	"hello.txt is {} bytes, and {} in the archive",
+	/// 	file_info.data().len(),
+	/// 	file_info.stored_data().len()
+	/// );
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub fn stored_data(&self) -> &[u8] {
+		self.stored.as_deref().unwrap_or(self.data())
+	}
+
+	/// Integrity details of the file, such as hashes.
+	///
+	/// ## Example
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let file_info = asar.read(Path::new("hello.txt")).unwrap();
+	/// let integrity = file_info.integrity().unwrap();
+	/// assert_eq!(
+	/// 	integrity.hash(),
+	/// 	b"\xf6\x95\x2d\x6e\xef\x55\x5d\xdd\x87\xac\xa6\x6e\x56\xb9\x15\x30\x22\
+	/// x2d\x6e\x31\x84\x14\x81\x6f\x3b\xa7\xcf\x5b\xf6\x94\xbf\x0f"
+	/// );
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub const fn integrity(&self) -> Option<&FileIntegrity> {
+		self.integrity.as_ref()
+	}
+
+	/// Hashes the current contents of the file with `algorithm`, whether or
+	/// not the archive stored integrity details for it.
+	///
+	/// ## Example
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{AsarReader, HashAlgorithm};
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let file_info = asar.read(Path::new("hello.txt")).unwrap();
+	/// let hash = file_info.compute_hash(HashAlgorithm::Sha256);
+	/// println!("hello.txt hash: {}", hex::encode(hash));
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[cfg(feature = "integrity")]
+	#[inline]
+	pub fn compute_hash(&self, algorithm: HashAlgorithm) -> Vec<u8> {
+		algorithm.hash(self.data())
+	}
+
+	/// Gets the block at `index` of the file, along with its expected hash, if
+	/// the file has integrity details, so that part of a file can be checked
+	/// without hashing the rest of it.
+	///
+	/// Blocks are numbered from 0, and are the size of the file's
+	/// [`FileIntegrity::block_size`], or 4 MiB if it has no integrity details.
+	/// The last block may be smaller. Returns `None` if the file has no block
+	/// at `index`. Like the integrity details, blocks are of the
+	/// [`AsarFile::stored_data`].
+	///
+	/// ## Example
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{AsarReader, HashAlgorithm};
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// let file_info = asar.read(Path::new("video.mp4")).unwrap();
+	/// let (block, expected_hash) = file_info.block(3).unwrap();
+	/// assert_eq!(
+	/// 	Some(HashAlgorithm::Sha256.hash(block).as_slice()),
+	/// 	expected_hash
+	/// );
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn block(&self, index: usize) -> Option<(&[u8], Option<&[u8]>)> {
+		let block_size = self
+			.integrity
+			.as_ref()
+			.map_or(DEFAULT_BLOCK_SIZE, FileIntegrity::block_size);
+		if block_size == 0 {
+			return None;
+		}
+		let block = self.stored_data().chunks(block_size).nth(index)?;
+		let hash = self
+			.integrity
+			.as_ref()
+			.and_then(|integrity| integrity.blocks().get(index))
+			.map(Vec::as_slice);
+		Some((block, hash))
+	}
+
+	/// The offset of the file from the start of the asar's data region, or
+	/// `None` if it's unpacked.
+	///
+	/// See [`File::offset`](crate::header::File::offset).
+	#[inline]
+	pub const fn offset(&self) -> Option<usize> {
+		match self.location {
+			FileLocation::Offset { offset } => Some(offset),
+			FileLocation::Unpacked { .. } => None,
+		}
+	}
+
+	/// Whether this file is unpacked, meaning it's stored next to the asar
+	/// rather than in it.
+	///
+	/// ## Example
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::AsarReader;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// # let asar = AsarReader::new(&asar_file, None)?;
+	/// for (path, file) in asar.files() {
+	/// 	if file.unpacked() {
+	/// 		println!("{} is unpacked", path.display());
+	/// 	}
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub const fn unpacked(&self) -> bool {
+		matches!(self.location, FileLocation::Unpacked { .. })
+	}
+
+	/// Whether this file is executable or not.
+	#[inline]
+	pub const fn executable(&self) -> bool {
+		self.executable
+	}
+
+	/// The modification time of the file, if it was recorded when the archive
+	/// was written.
+	///
+	/// See [`File::mtime`](crate::header::File::mtime).
+	#[inline]
+	pub const fn mtime(&self) -> Option<SystemTime> {
+		self.mtime
+	}
+}
+
+fn recursive_read<'a>(
+	path: PathBuf,
+	file_map: &mut BTreeMap<PathBuf, AsarFile<'a>>,
+	dir_map: &mut BTreeMap<PathBuf, Vec<PathBuf>>,
+	symlink_map: &mut BTreeMap<PathBuf, PathBuf>,
+	header: &Header,
+	begin_offset: usize,
+	data: &'a [u8],
 	asar_path: Option<&Path>,
+	unpacked_policy: UnpackedPolicy,
 ) -> Result<()> {
 	match header {
+		Header::File(_) | Header::Link { .. }
+			if file_map.contains_key(&path)
+				|| dir_map.contains_key(&path)
+				|| symlink_map.contains_key(&path) =>
+		{
+			return Err(Error::DuplicateEntry(path));
+		}
+		Header::Directory { .. }
+			if file_map.contains_key(&path) || symlink_map.contains_key(&path) =>
+		{
+			return Err(Error::DuplicateEntry(path));
+		}
 		Header::File(file) => {
+			#[cfg(feature = "integrity")]
+			if let (Some(_), Some(integrity)) = (file.offset(), file.integrity()) {
+				crate::integrity::check_blocks(&path, file.size(), integrity)?;
+			}
 			let data = match file.location() {
 				FileLocation::Offset { offset } => {
-					let start = begin_offset + offset;
-					let end = start + file.size();
+					let overflow = || Error::OffsetOverflow {
+						path: path.clone(),
+						offset,
+						size: file.size(),
+					};
+					let start = begin_offset.checked_add(offset).ok_or_else(overflow)?;
+					let end = start.checked_add(file.size()).ok_or_else(overflow)?;
 					if data.len() < end {
-						println!(
-							"file truncated path='{}', data_len={}, start={}, size={}, end={}",
-							path.display(),
-							data.len(),
-							start,
-							file.size(),
-							end
-						);
 						return Err(Error::Truncated);
 					}
 					Cow::Borrowed(&data[start..end])
 				}
 				FileLocation::Unpacked { .. } => match asar_path {
 					Some(asar_path) => {
-						std::fs::read(asar_path.with_extension("asar.unpacked").join(&path))
-							.map(Cow::Owned)
-							.map_err(|err| Error::UnpackedIoError {
-								path: path.clone(),
-								err,
-							})?
+						match std::fs::read(asar_path.with_extension("asar.unpacked").join(&path)) {
+							Ok(data) => Cow::Owned(data),
+							Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+								match unpacked_policy {
+									UnpackedPolicy::Fail => {
+										return Err(Error::UnpackedIoError { path, err })
+									}
+									UnpackedPolicy::SkipMissing => {
+										if let Some(siblings) =
+											path.parent().and_then(|parent| dir_map.get_mut(parent))
+										{
+											siblings.retain(|sibling| sibling != &path);
+										}
+										return Ok(());
+									}
+									UnpackedPolicy::Empty => Cow::Borrowed(&[] as &[u8]),
+								}
+							}
+							Err(err) => return Err(Error::UnpackedIoError { path, err }),
+						}
 					}
 					None => Cow::Borrowed(&[] as &[u8]),
 				},
 			};
 			#[cfg(feature = "check-integrity-on-read")]
-			{
-				let integrity = file.integrity();
-				let algorithm = integrity.algorithm();
-				let block_size = integrity.block_size();
-				let blocks = integrity.blocks();
-				if block_size > 0 && !blocks.is_empty() {
-					for (idx, (block, expected_hash)) in
-						data.chunks(block_size).zip(blocks.iter()).enumerate()
-					{
-						let hash = algorithm.hash(block);
-						if hash != *expected_hash {
-							return Err(Error::HashMismatch {
-								file: path,
-								block: Some(idx + 1),
-								expected: expected_hash.to_owned(),
-								actual: hash,
-							});
-						}
-					}
-				}
-				let hash = algorithm.hash(data);
-				if hash != integrity.hash() {
-					return Err(Error::HashMismatch {
-						file: path,
-						block: None,
-						expected: integrity.hash().to_owned(),
-						actual: hash,
-					});
-				}
+			if let Some(integrity) = file.integrity() {
+				crate::integrity::verify(&path, &data, integrity)?;
 			}
+			let (data, stored) = match file.compression() {
+				Some(compression) => (
+					Cow::Owned(crate::compression::decompress(&path, &data, compression)?),
+					Some(data),
+				),
+				None => (data, None),
+			};
 			file_map.insert(path, AsarFile {
 				data,
+				stored,
+				location: file.location(),
 				integrity: file.integrity().cloned(),
+				executable: file.executable(),
+				mtime: file.mtime(),
 			});
 		}
 		Header::Directory { files } => {
+			// Directories are listed even when they're empty, so that the root
+			// is always there.
+			dir_map.entry(path.clone()).or_default();
 			for (name, header) in files {
 				let file_path = path.join(name);
 				dir_map
@@ -370,6 +1804,7 @@ fn recursive_read<'a>(
 					begin_offset,
 					data,
 					asar_path,
+					unpacked_policy,
 				)?;
 			}
 		}
@@ -382,9 +1817,18 @@ fn recursive_read<'a>(
 
 #[cfg(test)]
 pub mod test {
-	use super::AsarReader;
-	use crate::header::TEST_ASAR;
+	#[cfg(feature = "write")]
+	use super::AsarFile;
+	use super::{AsarReader, DirEntry, EntryKind, ReaderLimits, UnpackedPolicy};
+	#[cfg(feature = "write")]
+	use crate::header::DEFAULT_BLOCK_SIZE;
+	use crate::{
+		error::{Error, PartialError},
+		header::{Header, TEST_ASAR},
+		test_util::{frame, temp_path},
+	};
 	use include_dir::{include_dir, Dir};
+	use std::path::{Path, PathBuf};
 
 	static ASAR_CONTENTS: Dir = include_dir!("$CARGO_MANIFEST_DIR/data/contents");
 
@@ -400,4 +1844,888 @@ pub mod test {
 			assert_eq!(real_contents, asar_contents);
 		}
 	}
+
+	#[test]
+	fn test_entry_kind() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		assert_eq!(
+			reader.entry_kind(Path::new("file 1.txt")),
+			Some(EntryKind::File)
+		);
+		assert_eq!(
+			reader.entry_kind(Path::new("folder 1/folder 2")),
+			Some(EntryKind::Directory)
+		);
+		assert_eq!(
+			reader.entry_kind(Path::new("symlink 1")),
+			Some(EntryKind::Symlink)
+		);
+		assert_eq!(reader.entry_kind(Path::new("file 3.txt")), None);
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_block() {
+		use crate::{header::HashAlgorithm, writer::AsarWriter};
+
+		let data = (0..10 * 1024 * 1024_u32)
+			.map(|n| n as u8)
+			.collect::<Vec<_>>();
+		let mut writer = AsarWriter::new();
+		writer
+			.write_file("big.bin", &data, false)
+			.expect("failed to write file");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		let file = &reader.files()[Path::new("big.bin")];
+		let block_size = DEFAULT_BLOCK_SIZE;
+		for index in 0..3 {
+			let (block, hash) = file.block(index).expect("block should exist");
+			let start = index * block_size;
+			let end = (start + block_size).min(data.len());
+			assert_eq!(block, &data[start..end]);
+			assert_eq!(hash, Some(HashAlgorithm::Sha256.hash(block).as_slice()));
+		}
+		assert_eq!(file.block(3), None);
+	}
+
+	#[test]
+	fn test_files_by_offset() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let files = reader.files_by_offset();
+		assert_eq!(files.len(), reader.files().len());
+		let mut expected_offset = 0;
+		for (path, file) in files {
+			assert_eq!(file.offset(), Some(expected_offset), "{}", path.display());
+			expected_offset += file.data().len();
+		}
+	}
+
+	#[test]
+	fn test_get_or_resolve() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let file = reader.files().get(Path::new("file 1.txt"));
+		assert_eq!(reader.get_or_resolve(Path::new("file 1.txt")), file);
+		let file = reader.files().get(Path::new("folder 1/file 3.txt"));
+		assert!(file.is_some());
+		assert_eq!(reader.get_or_resolve(Path::new("symlink 1")), file);
+		assert_eq!(reader.get_or_resolve(Path::new("folder 1")), None);
+		assert_eq!(reader.get_or_resolve(Path::new("file 3.txt")), None);
+	}
+
+	#[test]
+	fn test_symlink_target() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		assert_eq!(
+			reader.symlink_target(Path::new("symlink 1")),
+			Some(PathBuf::from("folder 1/file 3.txt"))
+		);
+		assert_eq!(reader.symlink_target(Path::new("file 1.txt")), None);
+
+		let header: Header = serde_json::from_str(
+			r#"{"files":{"a":{"files":{"b":{"size":3,"offset":"0"}}},"c":{"link":"./a/../a/./b"},"d":{"link":"./a/../../a/./b"},"e":{"link":"/a/b"}}}"#,
+		)
+		.expect("failed to parse header");
+		let reader =
+			AsarReader::new_from_header(header, 0, b"abc", None).expect("failed to read asar");
+		let target = reader
+			.symlink_target(Path::new("c"))
+			.expect("c should be a symbolic link");
+		assert_eq!(target, Path::new("a/b"));
+		assert!(reader.files().contains_key(&target));
+		// `d` climbs out of the root, rather than staying at it.
+		assert_eq!(reader.symlink_target(Path::new("d")), None);
+		assert_eq!(
+			reader.symlink_target(Path::new("e")),
+			Some(PathBuf::from("a/b"))
+		);
+	}
+
+	#[test]
+	fn test_file_root() {
+		let header: Header =
+			serde_json::from_str(r#"{"size":3,"offset":"0"}"#).expect("failed to parse header");
+		assert!(matches!(header, Header::File(_)));
+		assert_eq!(
+			AsarReader::new_from_header(header, 0, b"abc", None),
+			Err(Error::InvalidRoot(EntryKind::File))
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "integrity")]
+	fn test_inconsistent_blocks() {
+		let hash = "00".repeat(32);
+		let header: Header = serde_json::from_str(&format!(
+			r#"{{"files":{{"a.bin":{{"size":10,"offset":"0","integrity":{{"algorithm":"SHA256","hash":"{hash}","blockSize":4,"blocks":["{hash}"]}}}}}}}}"#
+		))
+		.expect("failed to parse header");
+		assert_eq!(
+			AsarReader::new_from_header(header, 0, &[0; 10], None),
+			Err(Error::InvalidIntegrity {
+				path: "a.bin".into(),
+				reason: "expected 3 blocks of 4 bytes, found 1".to_string(),
+			})
+		);
+	}
+
+	#[test]
+	fn test_link_root() {
+		let header: Header =
+			serde_json::from_str(r#"{"link":"app"}"#).expect("failed to parse header");
+		assert!(matches!(header, Header::Link { .. }));
+		let err = AsarReader::new_from_header(header, 0, b"", None).unwrap_err();
+		assert_eq!(err, Error::InvalidRoot(EntryKind::Symlink));
+		assert_eq!(
+			err.to_string(),
+			"The root of the archive is a symbolic link, rather than a directory"
+		);
+	}
+
+	#[test]
+	fn test_try_new() {
+		let (_, offset) = Header::read(&mut &*TEST_ASAR).expect("failed to read header");
+		let json_size = u32::from_le_bytes(TEST_ASAR[12..16].try_into().unwrap()) as usize;
+		let mut expected = vec![16, 16 + json_size];
+		let manifest = AsarReader::new(TEST_ASAR, None)
+			.expect("failed to read asar")
+			.manifest();
+		let mut ends = manifest
+			.files
+			.iter()
+			.map(|file| offset + file.offset.unwrap() + file.size)
+			.collect::<Vec<_>>();
+		ends.sort();
+		expected.extend(ends);
+
+		let mut len = 0;
+		let mut required = Vec::new();
+		loop {
+			match AsarReader::try_new(&TEST_ASAR[..len]) {
+				Ok(reader) => {
+					assert_eq!(reader.files().len(), manifest.files.len());
+					break;
+				}
+				Err(PartialError::NeedMore { required_len }) => {
+					assert!(required_len > len);
+					required.push(required_len);
+					len = required_len;
+				}
+				Err(PartialError::Error(err)) => panic!("failed to read asar: {err}"),
+			}
+		}
+		assert_eq!(len, TEST_ASAR.len());
+		assert_eq!(required, expected);
+	}
+
+	#[test]
+	fn test_offset_overflow() {
+		let json = format!(
+			r#"{{"files":{{"a.txt":{{"size":3,"offset":"{}"}}}}}}"#,
+			usize::MAX
+		);
+		let asar = frame(json.as_bytes(), b"abc");
+		assert_eq!(
+			AsarReader::new(&asar, None),
+			Err(Error::OffsetOverflow {
+				path: "a.txt".into(),
+				offset: usize::MAX,
+				size: 3
+			})
+		);
+		let json = format!(
+			r#"{{"files":{{"a.txt":{{"size":{},"offset":"1"}}}}}}"#,
+			usize::MAX
+		);
+		let asar = frame(json.as_bytes(), b"abc");
+		assert_eq!(
+			AsarReader::new(&asar, None),
+			Err(Error::OffsetOverflow {
+				path: "a.txt".into(),
+				offset: 1,
+				size: usize::MAX
+			})
+		);
+	}
+
+	#[test]
+	fn test_unpacked_policy() {
+		let json = br#"{"files":{"a.txt":{"size":3,"offset":"0"},"dir":{"files":{"b.txt":{"size":3,"unpacked":true}}}}}"#;
+		let asar = frame(json, b"abc");
+		let asar_path = temp_path("unpacked-policy.asar");
+		let read = |policy| AsarReader::new_with_unpacked_policy(&asar, asar_path.clone(), policy);
+
+		assert!(matches!(
+			read(UnpackedPolicy::Fail),
+			Err(Error::UnpackedIoError { .. })
+		));
+		let skipped = read(UnpackedPolicy::SkipMissing).expect("failed to read asar");
+		assert_eq!(skipped.files().len(), 1);
+		assert_eq!(skipped.files()[Path::new("a.txt")].data(), b"abc");
+		assert!(skipped
+			.read_dir(Path::new("dir"))
+			.is_some_and(|entries| entries.is_empty()));
+		let empty = read(UnpackedPolicy::Empty).expect("failed to read asar");
+		assert_eq!(empty.files().len(), 2);
+		assert!(empty.files()[Path::new("dir/b.txt")].data().is_empty());
+	}
+
+	#[test]
+	#[cfg(all(feature = "normalize-unicode", feature = "write"))]
+	fn test_normalize_unicode() {
+		let nfd = Path::new("re\u{301}sume\u{301}.txt");
+		let nfc = Path::new("r\u{e9}sum\u{e9}.txt");
+
+		let mut writer = crate::writer::AsarWriter::new();
+		writer
+			.write_file(nfd, b"Don't Panic.", false)
+			.expect("failed to write file");
+		let bytes = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+		assert!(reader.files().contains_key(nfd));
+		assert_eq!(reader.read(nfc), None);
+		assert_eq!(
+			reader.read_normalized(nfc).map(AsarFile::data),
+			Some(&b"Don't Panic."[..])
+		);
+
+		let mut writer = crate::writer::AsarWriter::builder()
+			.normalize_unicode(true)
+			.build();
+		writer
+			.write_file(nfd, b"Don't Panic.", false)
+			.expect("failed to write file");
+		assert_eq!(
+			writer.write_file(nfc, b"Mostly harmless.", false),
+			Err(Error::FileAlreadyWritten(nfc.to_path_buf()))
+		);
+		writer
+			.write_symlink("link", nfd)
+			.expect("failed to write symlink");
+		let bytes = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+		assert!(reader.files().contains_key(nfc));
+		assert_eq!(reader.symlinks()[Path::new("link")], nfc);
+		assert!(reader.read_normalized(nfd).is_some());
+	}
+
+	#[test]
+	fn test_max_depth() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		assert_eq!(reader.max_depth(), 3);
+		assert_eq!(
+			reader.deepest_path(),
+			Some(Path::new("folder 1/folder 2/file 4.txt"))
+		);
+
+		let header: Header =
+			serde_json::from_str(r#"{"files":{}}"#).expect("failed to parse header");
+		let reader =
+			AsarReader::new_from_header(header, 0, &[], None).expect("failed to read asar");
+		assert_eq!(reader.max_depth(), 0);
+		assert_eq!(reader.deepest_path(), None);
+
+		let header: Header = serde_json::from_str(
+			r#"{"files":{"a":{"files":{"b":{"files":{}}}},"c":{"link":"a"}}}"#,
+		)
+		.expect("failed to parse header");
+		let reader =
+			AsarReader::new_from_header(header, 0, &[], None).expect("failed to read asar");
+		assert_eq!(reader.max_depth(), 2);
+		assert_eq!(reader.deepest_path(), Some(Path::new("a/b")));
+	}
+
+	#[test]
+	fn test_root_directory() {
+		let header: Header =
+			serde_json::from_str(r#"{"files":{}}"#).expect("failed to parse header");
+		let reader =
+			AsarReader::new_from_header(header, 0, &[], None).expect("failed to read asar");
+		assert_eq!(reader.directories().len(), 1);
+		assert_eq!(reader.read_dir(Path::new("")), Some(&[][..]));
+
+		let header: Header = serde_json::from_str(r#"{"files":{"empty":{"files":{}}}}"#)
+			.expect("failed to parse header");
+		let reader =
+			AsarReader::new_from_header(header, 0, &[], None).expect("failed to read asar");
+		assert_eq!(
+			reader.read_dir(Path::new("")),
+			Some(&[PathBuf::from("empty")][..])
+		);
+		assert_eq!(reader.read_dir(Path::new("empty")), Some(&[][..]));
+	}
+
+	#[test]
+	fn test_file_at_offset() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		for (path, file) in reader.files() {
+			let offset = file.offset().expect("test.asar has no unpacked files");
+			assert_eq!(reader.file_at_offset(offset), Some((path.as_path(), file)));
+		}
+		assert_eq!(reader.file_at_offset(1), None);
+
+		let header: Header = serde_json::from_str(
+			r#"{"files":{"a":{"size":0,"offset":"0"},"b":{"size":3,"offset":"0"},"c":{"size":0,"offset":"3"}}}"#,
+		)
+		.expect("failed to parse header");
+		let reader =
+			AsarReader::new_from_header(header, 0, b"abc", None).expect("failed to read asar");
+		assert_eq!(
+			reader.file_at_offset(0).map(|(path, _)| path),
+			Some(Path::new("b"))
+		);
+		assert_eq!(
+			reader.file_at_offset(3).map(|(path, _)| path),
+			Some(Path::new("c"))
+		);
+	}
+
+	#[test]
+	fn test_into_iter() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let files = (&reader).into_iter().collect::<Vec<_>>();
+		assert_eq!(files.len(), reader.files().len());
+		for ((path, file), (expected_path, expected_file)) in files.iter().zip(reader.files()) {
+			assert_eq!(*path, expected_path.as_path());
+			assert_eq!(*file, expected_file);
+		}
+		assert_eq!(
+			(&reader).into_iter().next_back().map(|(path, _)| path),
+			reader.files().keys().next_back().map(PathBuf::as_path)
+		);
+	}
+
+	#[test]
+	#[cfg(not(feature = "compression"))]
+	fn test_compressed_without_feature() {
+		let asar = frame(
+			br#"{"files":{"a.txt":{"size":3,"offset":"0","asar-rs:compression":{"method":"gzip","originalSize":3}}}}"#,
+			b"abc",
+		);
+		assert!(matches!(
+			AsarReader::new(&asar, None),
+			Err(Error::Decompression { .. })
+		));
+	}
+
+	#[test]
+	#[cfg(all(feature = "compression", feature = "write"))]
+	fn test_verify_compressed() {
+		use crate::{
+			header::{CompressionMethod, HashAlgorithm},
+			streaming::StreamingAsarReader,
+			AsarWriter,
+		};
+		use std::io::Cursor;
+
+		let data = b"Don't Panic. ".repeat(1000);
+		let mut writer = AsarWriter::builder()
+			.compress(CompressionMethod::Gzip)
+			.build();
+		writer
+			.write_file("advice.txt", &data, false)
+			.expect("failed to write file");
+		let asar = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&asar, None).expect("failed to read asar");
+		let path = Path::new("advice.txt");
+		let file = &reader.files()[path];
+		assert_eq!(file.data(), data);
+		assert_ne!(file.stored_data(), data);
+
+		// The integrity is of the compressed contents, so every reader checks
+		// those, rather than what they decompress to.
+		assert!(reader.verify_iter().all(|(_, result)| result.is_ok()));
+		assert_eq!(reader.verify_file(path), Ok(()));
+		let (block, hash) = file.block(0).expect("block should exist");
+		assert_eq!(block, file.stored_data());
+		assert_eq!(hash, Some(HashAlgorithm::Sha256.hash(block).as_slice()));
+		let mut streaming =
+			StreamingAsarReader::new(Cursor::new(&asar)).expect("failed to read asar");
+		assert!(streaming.verify_file(path).is_ok());
+	}
+
+	#[test]
+	fn test_limits() {
+		let read = |limits| AsarReader::new_with_limits(TEST_ASAR, None, limits);
+		let json_size = u32::from_le_bytes(TEST_ASAR[12..16].try_into().unwrap()) as usize;
+		let reader = read(ReaderLimits {
+			max_header_size: Some(json_size),
+			max_entries: Some(11),
+			max_file_size: Some(34),
+		})
+		.expect("test.asar should be within the limits");
+		assert_eq!(reader.files().len(), 6);
+
+		assert_eq!(
+			read(ReaderLimits {
+				max_header_size: Some(json_size - 1),
+				..ReaderLimits::default()
+			}),
+			Err(Error::HeaderTooLarge {
+				size: json_size,
+				max: json_size - 1
+			})
+		);
+		assert_eq!(
+			read(ReaderLimits {
+				max_entries: Some(10),
+				..ReaderLimits::default()
+			}),
+			Err(Error::TooManyEntries { count: 11, max: 10 })
+		);
+		assert_eq!(
+			read(ReaderLimits {
+				max_file_size: Some(33),
+				..ReaderLimits::default()
+			}),
+			Err(Error::FileTooLarge {
+				path: "folder 1/folder 2/file 5.txt".into(),
+				size: 34,
+				max: 33
+			})
+		);
+
+		// A compressed file can't claim to decompress to more than the limit.
+		let asar = frame(
+			br#"{"files":{"bomb":{"size":3,"offset":"0","asar-rs:compression":{"method":"gzip","originalSize":1000000}}}}"#,
+			b"abc",
+		);
+		assert_eq!(
+			AsarReader::new_with_limits(&asar, None, ReaderLimits {
+				max_file_size: Some(1024),
+				..ReaderLimits::default()
+			}),
+			Err(Error::FileTooLarge {
+				path: "bomb".into(),
+				size: 1_000_000,
+				max: 1024
+			})
+		);
+	}
+
+	#[test]
+	fn test_new_from_header_ref() {
+		let (header, offset) = Header::read(&mut &*TEST_ASAR).expect("failed to read header");
+		let borrowed = AsarReader::new_from_header_ref(&header, offset, TEST_ASAR, None)
+			.expect("failed to read asar");
+		let owned = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		assert_eq!(borrowed.files(), owned.files());
+		assert_eq!(borrowed.symlinks(), owned.symlinks());
+		assert_eq!(borrowed.header(), &header);
+	}
+
+	#[test]
+	fn test_new_at() {
+		let mut embedded = b"MZ not really an executable".to_vec();
+		let start_offset = embedded.len();
+		embedded.extend_from_slice(TEST_ASAR);
+		embedded.extend_from_slice(b"trailing data");
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let embedded_reader =
+			AsarReader::new_at(&embedded, start_offset, None).expect("failed to read asar");
+		assert_eq!(embedded_reader.files(), reader.files());
+		assert_eq!(
+			AsarReader::new_at(&embedded, embedded.len() + 1, None),
+			Err(Error::HeaderTruncated)
+		);
+	}
+
+	#[test]
+	fn test_root() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let mut root = reader.root().to_vec();
+		root.sort();
+		assert_eq!(
+			root,
+			[
+				"file 1.txt",
+				"file 2.txt",
+				"folder 1",
+				"symlink 1",
+				"symlink 2"
+			]
+			.map(PathBuf::from)
+		);
+
+		let header: Header = serde_json::from_str(r#"{"files":{}}"#).unwrap();
+		let empty = AsarReader::new_from_header(header, 0, b"", None).expect("failed to read asar");
+		assert!(empty.root().is_empty());
+	}
+
+	#[test]
+	fn test_read_dir_entries() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let mut entries = reader
+			.read_dir_entries(Path::new("folder 1"))
+			.expect("failed to read directory");
+		entries.sort_by(|a, b| a.path.cmp(&b.path));
+		assert_eq!(entries, vec![
+			DirEntry {
+				path: PathBuf::from("folder 1/file 3.txt"),
+				kind: EntryKind::File,
+				size: Some(
+					reader.files()[Path::new("folder 1/file 3.txt")]
+						.data()
+						.len()
+				),
+			},
+			DirEntry {
+				path: PathBuf::from("folder 1/folder 2"),
+				kind: EntryKind::Directory,
+				size: None,
+			},
+			DirEntry {
+				path: PathBuf::from("folder 1/folder 3"),
+				kind: EntryKind::Directory,
+				size: None,
+			},
+		]);
+		assert_eq!(reader.read_dir_entries(Path::new("file 1.txt")), None);
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_source_map_for() {
+		let mut writer = crate::writer::AsarWriter::new();
+		for (path, contents) in [
+			("dist/main.js", "console.log(42)"),
+			("dist/main.js.map", r#"{"version":3}"#),
+			("dist/util.js", "export {}"),
+			("dist/main.map", "not a source map"),
+		] {
+			writer
+				.write_file(path, contents, false)
+				.expect("failed to write file");
+		}
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(
+			reader
+				.source_map_for(Path::new("dist/main.js"))
+				.map(AsarFile::data),
+			Some(&br#"{"version":3}"#[..])
+		);
+		assert!(reader.source_map_for(Path::new("dist/util.js")).is_none());
+		assert!(reader.source_map_for(Path::new("missing.js")).is_none());
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_detect_symlink_cycles() {
+		let mut writer = crate::writer::AsarWriter::new();
+		for (path, link) in [
+			("b", "a"),
+			("a", "b"),
+			("c", "a"),
+			("x/1", "x/2"),
+			("x/2", "x/3"),
+			("x/3", "x/1"),
+			("self", "self"),
+			("fine", "file.txt"),
+		] {
+			writer
+				.write_symlink(path, link)
+				.expect("failed to write symlink");
+		}
+		writer
+			.write_file("file.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.detect_symlink_cycles(), vec![
+			vec![PathBuf::from("a"), PathBuf::from("b")],
+			vec![PathBuf::from("self")],
+			vec![
+				PathBuf::from("x/1"),
+				PathBuf::from("x/2"),
+				PathBuf::from("x/3")
+			],
+		]);
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_resolve() {
+		let mut writer = crate::writer::AsarWriter::new();
+		writer
+			.write_file("dir/file.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		for (path, link) in [
+			("file link", "dir/file.txt"),
+			("link to link", "file link"),
+			("dir link", "dir"),
+			("loop a", "loop b"),
+			("loop b", "loop a"),
+		] {
+			writer
+				.write_symlink(path, link)
+				.expect("failed to write symlink");
+		}
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+
+		let file = Path::new("dir/file.txt");
+		assert_eq!(reader.resolve(file), Ok(file.to_path_buf()));
+		assert_eq!(
+			reader.resolve(Path::new("link to link")),
+			Ok(file.to_path_buf())
+		);
+		assert_eq!(
+			reader.resolve(Path::new("dir link/file.txt")),
+			Ok(file.to_path_buf())
+		);
+		assert_eq!(reader.resolve(Path::new("dir link")), Ok("dir".into()));
+		assert_eq!(
+			reader.resolve(Path::new("loop a")),
+			Err(Error::SymlinkLoop("loop a".into()))
+		);
+		assert_eq!(
+			reader
+				.read(Path::new("dir link/file.txt"))
+				.map(AsarFile::data),
+			Some(&b"Don't Panic."[..])
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_snapshot() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		assert_eq!(reader.snapshot(), None);
+
+		let mut writer = crate::writer::AsarWriter::new();
+		writer
+			.write_file("nested/v8_context_snapshot.bin", b"nope", false)
+			.expect("failed to write file");
+		writer
+			.write_file("snapshot_blob.bin", b"snapshot", false)
+			.expect("failed to write file");
+		let bytes = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+		assert_eq!(
+			reader.snapshot().map(AsarFile::data),
+			Some(&b"snapshot"[..])
+		);
+	}
+
+	#[test]
+	fn test_bytes_for() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		for (path, file) in reader.files() {
+			let bytes = reader
+				.bytes_for(path)
+				.expect("file should be in the archive");
+			assert_eq!(bytes, file.data());
+			assert!(TEST_ASAR.as_ptr_range().contains(&bytes.as_ptr()) || bytes.is_empty());
+		}
+		assert_eq!(reader.bytes_for(Path::new("missing.txt")), None);
+	}
+
+	#[test]
+	fn test_duplicate_entry() {
+		let json = br#"{"files":{"a":{"files":{"b":{"size":0,"offset":"0"}}},"a/b":{"size":0,"offset":"0"}}}"#;
+		let asar = frame(json, &[]);
+		assert_eq!(
+			AsarReader::new(&asar, None).map(|_| ()),
+			Err(Error::DuplicateEntry("a/b".into()))
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "integrity")]
+	fn test_verify_iter() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		assert_eq!(reader.verify_iter().count(), reader.files().len());
+		for (path, result) in reader.verify_iter() {
+			assert_eq!(result, Ok(()), "{} should be valid", path.display());
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "integrity")]
+	fn test_verify_file() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		for path in reader.files().keys() {
+			assert_eq!(reader.verify_file(path), Ok(()));
+		}
+		assert_eq!(
+			reader.verify_file(Path::new("missing.txt")),
+			Err(Error::FileNotFound("missing.txt".into()))
+		);
+	}
+
+	#[test]
+	// A corrupted archive can't be read at all with `check-integrity-on-read`.
+	#[cfg(all(feature = "integrity", not(feature = "check-integrity-on-read")))]
+	fn test_verify_file_corrupted() {
+		let path = Path::new("folder 1/file 3.txt");
+		let (header, offset) = Header::read(&mut &*TEST_ASAR).expect("failed to read asar header");
+		let file_offset = match header.find(path) {
+			Some(Header::File(file)) => file.offset().unwrap(),
+			_ => panic!("{} should be a file", path.display()),
+		};
+		let mut corrupted = TEST_ASAR.to_vec();
+		corrupted[offset + file_offset] ^= 0xff;
+		let reader = AsarReader::new(&corrupted, None).expect("failed to read asar");
+		assert!(matches!(
+			reader.verify_file(path),
+			Err(Error::HashMismatch { block: Some(1), .. })
+		));
+		assert_eq!(reader.verify_file(Path::new("file 1.txt")), Ok(()));
+	}
+
+	#[test]
+	#[cfg(feature = "integrity")]
+	fn test_compute_hash() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		for (path, file) in reader.files() {
+			let integrity = file.integrity().expect("test.asar should have integrity");
+			assert_eq!(
+				file.compute_hash(integrity.algorithm()),
+				integrity.hash(),
+				"{} should hash to its stored hash",
+				path.display()
+			);
+		}
+	}
+
+	#[test]
+	fn test_largest_files() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let largest = reader.largest_files(3);
+		assert_eq!(largest, vec![
+			(Path::new("folder 1/folder 2/file 5.txt"), 34),
+			(Path::new("folder 1/folder 3/file 6.txt"), 31),
+			(Path::new("folder 1/folder 2/file 4.txt"), 20),
+		]);
+		assert_eq!(reader.largest_files(100).len(), reader.files().len());
+		assert!(reader.largest_files(0).is_empty());
+	}
+
+	#[test]
+	#[cfg(feature = "check-integrity-on-read")]
+	fn test_block_hash_mismatch() {
+		use crate::{error::Error, header::HashAlgorithm};
+
+		let data = b"Don't Panic.";
+		let algorithm = HashAlgorithm::Sha256;
+		let header = serde_json::from_value(serde_json::json!({
+			"files": {
+				"advice.txt": {
+					"size": data.len(),
+					"offset": "0",
+					"integrity": {
+						"algorithm": "SHA256",
+						"hash": hex::encode(algorithm.hash(data)),
+						"blockSize": 5,
+						"blocks": [
+							hex::encode(algorithm.hash(b"Don't")),
+							hex::encode(algorithm.hash(b"Ponic")),
+							hex::encode(algorithm.hash(b".")),
+						],
+					},
+				},
+			},
+		}))
+		.expect("failed to decode header");
+		let result = AsarReader::new_from_header(header, 0, data, None);
+		assert_eq!(
+			result,
+			Err(Error::HashMismatch {
+				file: "advice.txt".into(),
+				block: Some(2),
+				byte_range: 5..10,
+				expected: algorithm.hash(b"Ponic"),
+				actual: algorithm.hash(b" Pani"),
+			})
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_electron_integrity() {
+		use super::ElectronIntegrity;
+		use crate::{header::HashAlgorithm, writer::AsarWriter};
+
+		assert_eq!(
+			AsarReader::new(TEST_ASAR, None)
+				.expect("failed to read asar")
+				.electron_integrity(),
+			None
+		);
+		let (json, _) = Header::read_raw(&mut &*TEST_ASAR).expect("failed to read header");
+		let expected = ElectronIntegrity::of_header(&json);
+		assert_eq!(expected.algorithm, HashAlgorithm::Sha256);
+		assert_eq!(expected.hash.len(), 32);
+
+		let package_json = serde_json::json!({
+			"name": "app",
+			"integrity": {
+				"algorithm": "SHA256",
+				"hash": hex::encode(&expected.hash),
+			},
+		});
+		let mut writer = AsarWriter::new();
+		writer
+			.write_json("package.json", &package_json)
+			.expect("failed to write package.json");
+		let out = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.electron_integrity(), Some(expected));
+
+		let mut writer = AsarWriter::new();
+		writer
+			.write_json(
+				"package.json",
+				&serde_json::json!({ "integrity": "sha512-..." }),
+			)
+			.expect("failed to write package.json");
+		let out = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.electron_integrity(), None);
+	}
+
+	#[test]
+	#[cfg(feature = "write")]
+	fn test_checksum() {
+		use crate::{header::HashAlgorithm, writer::AsarWriter};
+
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		// Write the files in reverse, so they end up at different offsets.
+		let mut writer = AsarWriter::new();
+		for (path, file) in reader.files().iter().rev() {
+			writer
+				.write_file(path, file.data(), false)
+				.expect("failed to write file");
+		}
+		for (path, link) in reader.symlinks() {
+			writer
+				.write_symlink(path, link)
+				.expect("failed to write symlink");
+		}
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reversed = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_ne!(&out[..], TEST_ASAR);
+		assert_eq!(
+			reader.checksum(HashAlgorithm::Sha256),
+			reversed.checksum(HashAlgorithm::Sha256)
+		);
+
+		let mut writer = AsarWriter::new();
+		writer
+			.write_file("file 1.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let other = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_ne!(
+			reader.checksum(HashAlgorithm::Sha256),
+			other.checksum(HashAlgorithm::Sha256)
+		);
+	}
 }
@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use crate::{error::Result, reader::ReaderLimits, streaming::StreamingAsarReader};
+use reqwest::{
+	blocking::{Client, Response},
+	header::{CONTENT_LENGTH, RANGE},
+	StatusCode,
+};
+use std::io::{self, Read, Seek, SeekFrom, Take};
+
+/// A [`StreamingAsarReader`] which reads an asar archive over HTTP, using
+/// `Range` requests to only download the parts of the archive that are needed.
+///
+/// See [`RemoteAsarReader::open`].
+pub type RemoteAsarReader = StreamingAsarReader<HttpRangeReader>;
+
+impl RemoteAsarReader {
+	/// Opens an asar archive hosted at `url`, downloading only its header.
+	///
+	/// File contents are downloaded when they're requested, with one request
+	/// per file, for exactly the bytes of that file. The server must support
+	/// `Range` requests.
+	///
+	/// ## Errors
+	///
+	///  - If a request fails, or the server doesn't support `Range` requests,
+	///    returns an [`Error::Io`](crate::Error::Io)
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::remote::RemoteAsarReader;
+	///
+	/// let mut reader = RemoteAsarReader::open("https://example.com/app.asar")?;
+	/// let package_json = reader.read_file("package.json")?;
+	/// println!("{}", String::from_utf8_lossy(&package_json));
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn open(url: impl Into<String>) -> Result<Self> {
+		Self::new_with_before_read(
+			HttpRangeReader::new(Client::new(), url),
+			ReaderLimits::default(),
+			HttpRangeReader::expect_read,
+		)
+	}
+}
+
+/// A [`Read`] + [`Seek`] source for a file hosted over HTTP, which issues a
+/// `Range` request for the bytes at the current position whenever it's read
+/// from after seeking, or after the last response has been read.
+///
+/// Each request is for as many bytes as the buffer being read into, so that
+/// nothing past what's needed is downloaded. Sequential reads continue from
+/// the same response until it ends.
+#[derive(Debug)]
+pub struct HttpRangeReader {
+	client: Client,
+	url: String,
+	position: u64,
+	/// The amount of bytes the next request asks for, if it's known how many
+	/// are about to be read.
+	next_len: Option<u64>,
+	response: Option<Take<Response>>,
+}
+
+impl HttpRangeReader {
+	/// Creates a new [`HttpRangeReader`] for the file at `url`, positioned at
+	/// the start of the file.
+	///
+	/// No requests are made until the reader is read from.
+	pub fn new(client: Client, url: impl Into<String>) -> Self {
+		Self {
+			client,
+			url: url.into(),
+			position: 0,
+			next_len: None,
+			response: None,
+		}
+	}
+
+	/// Makes the next request ask for the `len` bytes from the current
+	/// position, rather than as many as the buffer being read into, as
+	/// they're about to be read.
+	pub(crate) fn expect_read(&mut self, len: u64) {
+		self.next_len = (len > 0).then_some(len);
+		self.response = None;
+	}
+
+	/// Requests `len` bytes, which must be more than zero, starting at the
+	/// current position.
+	fn request(&self, len: u64) -> io::Result<Take<Response>> {
+		let end = self.position.saturating_add(len - 1);
+		let response = self
+			.client
+			.get(&self.url)
+			.header(RANGE, format!("bytes={}-{end}", self.position))
+			.send()
+			.and_then(Response::error_for_status)
+			.map_err(io::Error::other)?;
+		match response.status() {
+			StatusCode::PARTIAL_CONTENT => Ok(response.take(len)),
+			// A server without support for ranges sends the whole file, which
+			// starts with what we asked for if we're at the start of it.
+			StatusCode::OK if self.position == 0 => Ok(response.take(len)),
+			status => Err(io::Error::other(format!(
+				"server doesn't support range requests for {} (status {status})",
+				self.url
+			))),
+		}
+	}
+
+	fn len(&self) -> io::Result<u64> {
+		let response = self
+			.client
+			.head(&self.url)
+			.send()
+			.and_then(Response::error_for_status)
+			.map_err(io::Error::other)?;
+		response
+			.headers()
+			.get(CONTENT_LENGTH)
+			.and_then(|len| len.to_str().ok()?.parse().ok())
+			.ok_or_else(|| io::Error::other(format!("no content length for {}", self.url)))
+	}
+}
+
+impl Read for HttpRangeReader {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		if buf.is_empty() {
+			return Ok(0);
+		}
+		// A response which ended early has reached the end of the file, so
+		// only one which had all the bytes it was asked for is followed by
+		// another request.
+		if self
+			.response
+			.as_ref()
+			.is_none_or(|response| response.limit() == 0)
+		{
+			let len = self.next_len.take().unwrap_or(buf.len() as u64);
+			self.response = Some(self.request(len)?);
+		}
+		let response = self
+			.response
+			.as_mut()
+			.expect("a request should have just been made");
+		let read = response.read(buf)?;
+		self.position += read as u64;
+		Ok(read)
+	}
+}
+
+impl Seek for HttpRangeReader {
+	fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+		let position = match pos {
+			SeekFrom::Start(offset) => Some(offset),
+			SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+			SeekFrom::End(offset) => self.len()?.checked_add_signed(offset),
+		}
+		.ok_or_else(|| {
+			io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"invalid seek to a negative or overflowing position",
+			)
+		})?;
+		if position != self.position {
+			self.position = position;
+			self.response = None;
+		}
+		Ok(position)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::RemoteAsarReader;
+	use crate::{
+		header::{Header, TEST_ASAR},
+		reader::AsarReader,
+	};
+	use std::{
+		io::{BufRead, BufReader, Write},
+		net::TcpListener,
+		sync::{Arc, Mutex},
+		thread,
+	};
+
+	/// The first and last byte of each range requested from the test server.
+	type Ranges = Arc<Mutex<Vec<(usize, usize)>>>;
+
+	/// Serves [`TEST_ASAR`] over HTTP, with support for bounded `Range`
+	/// requests, returning its URL and the ranges requested so far.
+	fn serve_test_asar() -> (String, Ranges) {
+		let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+		let url = format!("http://{}/test.asar", listener.local_addr().unwrap());
+		let ranges = Arc::new(Mutex::new(Vec::new()));
+		let requested = Arc::clone(&ranges);
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut stream = stream.expect("failed to accept connection");
+				let mut range = None;
+				for line in BufReader::new(&stream).lines() {
+					let line = line.expect("failed to read request");
+					if line.is_empty() {
+						break;
+					}
+					if let Some(bytes) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+						let (start, end) = bytes.split_once('-').expect("range should have an end");
+						range = Some((start.parse().unwrap(), end.parse().unwrap()));
+					}
+				}
+				let (start, end): (usize, usize) = range.expect("request should have a range");
+				requested.lock().unwrap().push((start, end));
+				let body = &TEST_ASAR[start..=end.min(TEST_ASAR.len() - 1)];
+				let _ = write!(
+					stream,
+					"HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nConnection: \
+					 close\r\n\r\n",
+					body.len()
+				);
+				let _ = stream.write_all(body);
+			}
+		});
+		(url, ranges)
+	}
+
+	#[test]
+	fn test_remote_read() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let (url, ranges) = serve_test_asar();
+		let mut remote = RemoteAsarReader::open(url).expect("failed to open asar");
+		let framing = Header::read_framing(&mut &*TEST_ASAR).expect("failed to read framing");
+		// One request for the framing, and one for exactly the JSON.
+		assert_eq!(*ranges.lock().unwrap(), [
+			(0, 15),
+			(16, 15 + framing.json_size as usize)
+		]);
+		assert_eq!(remote.files().len(), reader.files().len());
+		let data_start = framing.payload_size as usize + 8;
+		for (path, file) in reader.files() {
+			ranges.lock().unwrap().clear();
+			let contents = remote.read_file(path).expect("failed to read file");
+			assert_eq!(contents, file.data());
+			let start = data_start + remote.files()[path].offset().unwrap();
+			assert_eq!(*ranges.lock().unwrap(), [(
+				start,
+				start + contents.len() - 1
+			)]);
+		}
+	}
+}
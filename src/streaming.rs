@@ -0,0 +1,468 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+#[cfg(feature = "integrity")]
+use crate::header::DEFAULT_BLOCK_SIZE;
+use crate::{
+	error::{Error, Result},
+	header::{File, Header},
+	reader::ReaderLimits,
+};
+use std::{
+	collections::BTreeMap,
+	io::{self, Read, Seek, SeekFrom, Write},
+	path::{Path, PathBuf},
+};
+
+/// A StreamingAsarReader reads an asar archive from a [`Read`] + [`Seek`]
+/// source, such as a [`std::fs::File`], without loading the whole archive into
+/// memory.
+///
+/// Only the header is read up front; file contents are read from the source
+/// when they're requested.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use asar::{Result, StreamingAsarReader};
+/// use std::fs::File;
+///
+/// fn main() -> Result<()> {
+/// 	let reader = StreamingAsarReader::new(File::open("archive.asar")?)?;
+///
+/// 	println!("There are {} files in archive.asar", reader.files().len());
+/// 	Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct StreamingAsarReader<R> {
+	source: R,
+	header: Header,
+	begin_offset: u64,
+	directories: BTreeMap<PathBuf, Vec<PathBuf>>,
+	files: BTreeMap<PathBuf, File>,
+	symlinks: BTreeMap<PathBuf, PathBuf>,
+	/// Called with the amount of bytes about to be read from the current
+	/// position of the source, so that sources which fetch ranges of the
+	/// archive, like `remote::HttpRangeReader`, can
+	/// fetch exactly those bytes.
+	before_read: fn(&mut R, u64),
+}
+
+impl<R: Read + Seek> StreamingAsarReader<R> {
+	/// Reads the header of an asar archive, starting at the current position
+	/// of `source`.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::StreamingAsarReader;
+	/// use std::fs::File;
+	///
+	/// let reader = StreamingAsarReader::new(File::open("archive.asar")?)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn new(source: R) -> Result<Self> {
+		Self::new_with_limits(source, ReaderLimits::default())
+	}
+
+	/// Reads the header of an asar archive like [`StreamingAsarReader::new`],
+	/// failing if it exceeds any of the given [`ReaderLimits`], which is
+	/// useful for reading untrusted archives safely.
+	///
+	/// As files are only read when they're requested, limiting their size
+	/// also limits how much memory [`StreamingAsarReader::read_file`] can
+	/// allocate.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarReader::new_with_limits`](crate::AsarReader::new_with_limits).
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{reader::ReaderLimits, StreamingAsarReader};
+	/// use std::fs::File;
+	///
+	/// let reader =
+	/// 	StreamingAsarReader::new_with_limits(File::open("untrusted.asar")?, ReaderLimits {
+	/// 		max_file_size: Some(256 * 1024 * 1024),
+	/// 		..ReaderLimits::default()
+	/// 	})?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn new_with_limits(source: R, limits: ReaderLimits) -> Result<Self> {
+		Self::new_with_before_read(source, limits, |_, _| {})
+	}
+
+	/// Reads the header of an asar archive like
+	/// [`StreamingAsarReader::new_with_limits`], calling `before_read` with
+	/// the amount of bytes about to be read whenever that's known.
+	pub(crate) fn new_with_before_read(
+		mut source: R,
+		limits: ReaderLimits,
+		before_read: fn(&mut R, u64),
+	) -> Result<Self> {
+		let start = source.stream_position()?;
+		// Only the 16 bytes of framing are known to be needed up front; the
+		// JSON is then read with a single `read_exact` of its size.
+		before_read(&mut source, 16);
+		let (header, offset) = Header::read_limited(&mut source, limits.max_header_size)?;
+		header.check_root()?;
+		limits.check(&header)?;
+		let mut files = BTreeMap::new();
+		let mut directories = BTreeMap::new();
+		let mut symlinks = BTreeMap::new();
+		recursive_read(
+			PathBuf::new(),
+			&mut files,
+			&mut directories,
+			&mut symlinks,
+			&header,
+		)?;
+		Ok(Self {
+			source,
+			header,
+			begin_offset: start + offset as u64,
+			directories,
+			files,
+			symlinks,
+			before_read,
+		})
+	}
+
+	/// The [`Header`] of the asar.
+	#[inline]
+	pub const fn header(&self) -> &Header {
+		&self.header
+	}
+
+	/// Gets all files in the asar, and their details.
+	#[inline]
+	pub const fn files(&self) -> &BTreeMap<PathBuf, File> {
+		&self.files
+	}
+
+	/// Gets all directories in the asar, including empty ones.
+	///
+	/// The root directory is always listed, under an empty path.
+	#[inline]
+	pub const fn directories(&self) -> &BTreeMap<PathBuf, Vec<PathBuf>> {
+		&self.directories
+	}
+
+	/// Gets all symbolic links in the asar.
+	#[inline]
+	pub const fn symlinks(&self) -> &BTreeMap<PathBuf, PathBuf> {
+		&self.symlinks
+	}
+
+	/// Copies the contents of a file in the asar to `writer`, returning the
+	/// amount of bytes copied.
+	///
+	/// The contents are streamed through a small, fixed-size buffer, and
+	/// compressed files are decompressed as they're copied.
+	///
+	/// ## Errors
+	///
+	///  - If the file doesn't exist or is unpacked, returns an
+	///    [`Error::FileNotFound`]
+	///  - If the archive ends before the file does, returns an
+	///    [`Error::Truncated`]
+	///  - If the file is compressed, and can't be decompressed, returns an
+	///    [`Error::Decompression`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::StreamingAsarReader;
+	/// use std::{fs::File, io};
+	///
+	/// let mut reader = StreamingAsarReader::new(File::open("archive.asar")?)?;
+	/// reader.copy_file_to("hello.txt", &mut io::stdout())?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn copy_file_to(&mut self, path: impl AsRef<Path>, writer: &mut impl Write) -> Result<u64> {
+		let path = path.as_ref();
+		let (file, offset) = self
+			.files
+			.get(path)
+			.and_then(|file| Some((file, file.offset()?)))
+			.ok_or_else(|| Error::FileNotFound(path.to_path_buf()))?;
+		let size = file.size();
+		let start = file_start(self.begin_offset, path, offset, size)?;
+		self.source.seek(SeekFrom::Start(start))?;
+		(self.before_read)(&mut self.source, size as u64);
+		let mut source = (&mut self.source).take(size as u64);
+		if let Some(compression) = file.compression() {
+			return crate::compression::decompress_to(path, source, compression, writer);
+		}
+		let copied = io::copy(&mut source, writer)?;
+		if copied != size as u64 {
+			return Err(Error::Truncated);
+		}
+		Ok(copied)
+	}
+
+	/// Reads the contents of a file in the asar into memory.
+	///
+	/// ## Errors
+	///
+	/// See [`StreamingAsarReader::copy_file_to`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::StreamingAsarReader;
+	/// use std::fs::File;
+	///
+	/// let mut reader = StreamingAsarReader::new(File::open("archive.asar")?)?;
+	/// let contents = reader.read_file("hello.txt")?;
+	/// assert_eq!(contents, b"Hello, World!");
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn read_file(&mut self, path: impl AsRef<Path>) -> Result<Vec<u8>> {
+		let mut contents = Vec::new();
+		self.copy_file_to(path, &mut contents)?;
+		Ok(contents)
+	}
+
+	/// Checks the contents of a file in the asar against its integrity
+	/// details, without reading the whole file into memory.
+	///
+	/// The file is read one block at a time, and each block is checked against
+	/// its hash as it's read, while the hash of the whole file is calculated
+	/// incrementally. Files without integrity details are assumed to be valid.
+	///
+	/// ## Errors
+	///
+	///  - If the file doesn't exist or is unpacked, returns an
+	///    [`Error::FileNotFound`]
+	///  - If a block, or the whole file, doesn't match its hash, returns an
+	///    [`Error::HashMismatch`]
+	///  - If the archive ends before the file does, returns an
+	///    [`Error::Truncated`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::StreamingAsarReader;
+	/// use std::fs::File;
+	///
+	/// let mut reader = StreamingAsarReader::new(File::open("archive.asar")?)?;
+	/// reader.verify_file("hello.txt")?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[cfg(feature = "integrity")]
+	pub fn verify_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+		let path = path.as_ref();
+		let file = self
+			.files
+			.get(path)
+			.filter(|file| !file.unpacked())
+			.ok_or_else(|| Error::FileNotFound(path.to_path_buf()))?;
+		let (Some(offset), Some(integrity)) = (file.offset(), file.integrity()) else {
+			return Ok(());
+		};
+		let size = file.size();
+		let algorithm = integrity.algorithm();
+		let block_size = match integrity.block_size() {
+			0 => DEFAULT_BLOCK_SIZE,
+			block_size => block_size,
+		};
+		let start = file_start(self.begin_offset, path, offset, size)?;
+		self.source.seek(SeekFrom::Start(start))?;
+
+		let mut hasher = algorithm.hasher();
+		let mut block = vec![0; block_size.min(size)];
+		let mut start = 0;
+		for idx in 0..size.div_ceil(block_size) {
+			let block = &mut block[..block_size.min(size - start)];
+			self.source
+				.read_exact(block)
+				.map_err(|err| match err.kind() {
+					io::ErrorKind::UnexpectedEof => Error::Truncated,
+					_ => Error::Io(err),
+				})?;
+			hasher.update(block);
+			if let Some(expected) = integrity.blocks().get(idx) {
+				let actual = algorithm.hash(block);
+				if actual != *expected {
+					return Err(Error::HashMismatch {
+						file: path.to_path_buf(),
+						block: Some(idx + 1),
+						byte_range: start..start + block.len(),
+						expected: expected.clone(),
+						actual,
+					});
+				}
+			}
+			start += block.len();
+		}
+		let actual = hasher.finalize();
+		if actual != integrity.hash() {
+			return Err(Error::HashMismatch {
+				file: path.to_path_buf(),
+				block: None,
+				byte_range: 0..size,
+				expected: integrity.hash().to_vec(),
+				actual,
+			});
+		}
+		Ok(())
+	}
+}
+
+/// Returns where the data of the file at `path` starts in the source, given
+/// where the file data begins, checking that the end of the file doesn't
+/// overflow.
+pub(crate) fn file_start(
+	begin_offset: u64,
+	path: &Path,
+	offset: usize,
+	size: usize,
+) -> Result<u64> {
+	begin_offset
+		.checked_add(offset as u64)
+		.filter(|start| start.checked_add(size as u64).is_some())
+		.ok_or_else(|| Error::OffsetOverflow {
+			path: path.to_path_buf(),
+			offset,
+			size,
+		})
+}
+
+pub(crate) fn recursive_read(
+	path: PathBuf,
+	file_map: &mut BTreeMap<PathBuf, File>,
+	dir_map: &mut BTreeMap<PathBuf, Vec<PathBuf>>,
+	symlink_map: &mut BTreeMap<PathBuf, PathBuf>,
+	header: &Header,
+) -> Result<()> {
+	match header {
+		Header::File(_) | Header::Link { .. }
+			if file_map.contains_key(&path)
+				|| dir_map.contains_key(&path)
+				|| symlink_map.contains_key(&path) =>
+		{
+			return Err(Error::DuplicateEntry(path));
+		}
+		Header::Directory { .. }
+			if file_map.contains_key(&path) || symlink_map.contains_key(&path) =>
+		{
+			return Err(Error::DuplicateEntry(path));
+		}
+		Header::File(file) => {
+			#[cfg(feature = "integrity")]
+			if let (Some(_), Some(integrity)) = (file.offset(), file.integrity()) {
+				crate::integrity::check_blocks(&path, file.size(), integrity)?;
+			}
+			file_map.insert(path, file.clone());
+		}
+		Header::Directory { files } => {
+			// Directories are listed even when they're empty, so that the root
+			// is always there.
+			dir_map.entry(path.clone()).or_default();
+			for (name, header) in files {
+				let file_path = path.join(name);
+				dir_map
+					.entry(path.clone())
+					.or_default()
+					.push(file_path.clone());
+				recursive_read(file_path, file_map, dir_map, symlink_map, header)?;
+			}
+		}
+		Header::Link { link } => {
+			symlink_map.insert(path, link.clone());
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::StreamingAsarReader;
+	#[cfg(feature = "integrity")]
+	use crate::{error::Error, header::Header};
+	use crate::{
+		extract::ExtractOptions, header::TEST_ASAR, reader::AsarReader, test_util::temp_path,
+	};
+	use std::io::Cursor;
+	#[cfg(feature = "integrity")]
+	use std::path::Path;
+
+	#[test]
+	fn test_streaming_read() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let mut streaming =
+			StreamingAsarReader::new(Cursor::new(TEST_ASAR)).expect("failed to read asar");
+		assert_eq!(streaming.files().len(), reader.files().len());
+		for (path, contents) in reader.directories() {
+			let mut expected = contents.clone();
+			let mut actual = streaming.directories()[path].clone();
+			expected.sort();
+			actual.sort();
+			assert_eq!(actual, expected);
+		}
+		assert_eq!(streaming.symlinks(), reader.symlinks());
+		for (path, file) in reader.files() {
+			let contents = streaming.read_file(path).expect("failed to read file");
+			assert_eq!(contents, file.data());
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "integrity")]
+	fn test_verify_file() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let mut streaming =
+			StreamingAsarReader::new(Cursor::new(TEST_ASAR)).expect("failed to read asar");
+		for path in reader.files().keys() {
+			streaming.verify_file(path).expect("file should be valid");
+		}
+
+		let path = Path::new("folder 1/file 3.txt");
+		let (_, offset) = Header::read(&mut &*TEST_ASAR).expect("failed to read asar header");
+		let mut corrupted = TEST_ASAR.to_vec();
+		corrupted[offset + streaming.files()[path].offset().unwrap()] ^= 0xff;
+		let mut streaming =
+			StreamingAsarReader::new(Cursor::new(corrupted)).expect("failed to read asar");
+		assert!(matches!(
+			streaming.verify_file(path),
+			Err(Error::HashMismatch { block: Some(1), .. })
+		));
+	}
+
+	#[test]
+	#[cfg(all(feature = "compression", feature = "write"))]
+	fn test_streaming_decompress() {
+		use crate::{header::CompressionMethod, writer::AsarWriter};
+
+		let data = b"Don't Panic. ".repeat(1000);
+		let mut writer = AsarWriter::builder()
+			.compress(CompressionMethod::Gzip)
+			.build();
+		writer
+			.write_file("advice.txt", &data, false)
+			.expect("failed to write file");
+		let asar = writer.into_bytes().expect("failed to finalize asar");
+		let mut streaming =
+			StreamingAsarReader::new(Cursor::new(asar)).expect("failed to read asar");
+		assert_eq!(streaming.read_file("advice.txt"), Ok(data));
+	}
+
+	#[test]
+	fn test_streaming_extract_to() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let mut streaming =
+			StreamingAsarReader::new(Cursor::new(TEST_ASAR)).expect("failed to read asar");
+		let destination = temp_path("streaming-extract-to");
+		streaming
+			.extract_to(&destination, &ExtractOptions::default())
+			.expect("failed to extract asar");
+		for (path, file) in reader.files() {
+			let contents =
+				std::fs::read(destination.join(path)).expect("failed to read extracted file");
+			assert_eq!(contents, file.data());
+		}
+		std::fs::remove_dir_all(&destination).expect("failed to clean up");
+	}
+}
@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use std::{fs, path::PathBuf};
+
+/// Frames a header's JSON and the data following it into an archive.
+pub(crate) fn frame(json: &[u8], data: &[u8]) -> Vec<u8> {
+	let mut asar = Vec::new();
+	for n in [
+		4,
+		json.len() as u32 + 8,
+		json.len() as u32 + 4,
+		json.len() as u32,
+	] {
+		asar.extend_from_slice(&n.to_le_bytes());
+	}
+	asar.extend_from_slice(json);
+	asar.extend_from_slice(data);
+	asar
+}
+
+/// Returns a path for a test to use in the temporary directory, removing
+/// anything a previous run of the test left there.
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+	let path = std::env::temp_dir().join(format!("asar-rs-test-{name}"));
+	let _ = fs::remove_dir_all(&path);
+	let _ = fs::remove_file(&path);
+	path
+}
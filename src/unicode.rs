@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+use std::{borrow::Cow, path::Path};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// Normalizes a path to Unicode Normalization Form C, borrowing it if it
+/// already is, or if it isn't valid UTF-8.
+pub(crate) fn nfc_path(path: &Path) -> Cow<'_, Path> {
+	match path.to_str() {
+		Some(path_str) if !is_nfc(path_str) => {
+			Cow::Owned(path_str.nfc().collect::<String>().into())
+		}
+		_ => Cow::Borrowed(path),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::nfc_path;
+	use std::path::Path;
+
+	#[test]
+	fn test_nfc_path() {
+		let nfd = Path::new("cafe\u{301}/re\u{301}sume\u{301}.txt");
+		let nfc = Path::new("caf\u{e9}/r\u{e9}sum\u{e9}.txt");
+		assert_eq!(nfc_path(nfd), nfc);
+		assert!(matches!(nfc_path(nfc), std::borrow::Cow::Borrowed(_)));
+	}
+}
@@ -1,24 +1,43 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
+#[cfg(feature = "compression")]
+use crate::header::{Compression, CompressionMethod};
 use crate::{
 	error::{Error, Result},
-	header::{File, FileIntegrity, FileLocation, HashAlgorithm, Header},
-	reader::AsarReader,
+	extract::normalize_path,
+	header::{File, FileIntegrity, FileLocation, HashAlgorithm, Header, DEFAULT_BLOCK_SIZE},
+	ordering::OrderingEntry,
+	reader::{AsarReader, EntryKind},
 };
 use byteorder::{LittleEndian, WriteBytesExt};
+use rayon::prelude::*;
+use serde::Serialize;
 use std::{
-	collections::{BTreeMap, VecDeque},
-	io::Write,
+	borrow::Cow,
+	collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+	fs,
+	io::{BufWriter, ErrorKind, Write},
 	path::{Component, Path, PathBuf},
+	time::SystemTime,
 };
-
-const BLOCK_SIZE: usize = 4 * 1024 * 1024; // 4 MiB
+use walkdir::WalkDir;
+use wax::{Glob, Pattern};
 
 pub struct AsarWriter {
 	files: BTreeMap<PathBuf, File>,
 	symlinks: BTreeMap<PathBuf, PathBuf>,
+	/// Files written with [`AsarWriter::write_file_deferred`], which are
+	/// hashed when the archive is finalized.
+	deferred: BTreeSet<PathBuf>,
 	buffer: Vec<u8>,
 	offset: usize,
 	hasher: HashAlgorithm,
+	data_alignment: usize,
+	pretty_header: bool,
+	lossy_paths: bool,
+	#[cfg(feature = "compression")]
+	compression: Option<CompressionMethod>,
+	#[cfg(feature = "normalize-unicode")]
+	normalize_unicode: bool,
 }
 
 impl AsarWriter {
@@ -33,17 +52,111 @@ impl AsarWriter {
 	///
 	/// Currently useless, as only one [`HashAlgorithm`] —
 	/// [`HashAlgorithm::Sha256`] — is supported
-	pub fn new_with_algorithm(hasher: HashAlgorithm) -> Self {
+	pub const fn new_with_algorithm(hasher: HashAlgorithm) -> Self {
 		Self {
 			files: BTreeMap::new(),
 			symlinks: BTreeMap::new(),
+			deferred: BTreeSet::new(),
 			buffer: Vec::new(),
 			offset: 0,
 			hasher,
+			data_alignment: DEFAULT_DATA_ALIGNMENT,
+			pretty_header: false,
+			lossy_paths: false,
+			#[cfg(feature = "compression")]
+			compression: None,
+			#[cfg(feature = "normalize-unicode")]
+			normalize_unicode: false,
 		}
 	}
 
-	/// Adds all the files from an [`AsarReader`] to the [`AsarWriter`].
+	/// Creates an [`AsarWriterBuilder`], for configuring how the archive is
+	/// written.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	///
+	/// let mut writer = AsarWriter::builder().data_alignment(8).build();
+	/// writer.write_file("advice.txt", b"Don't Panic.", false)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	#[inline]
+	pub const fn builder() -> AsarWriterBuilder {
+		AsarWriterBuilder::new()
+	}
+
+	/// Creates an archive containing just one file, returning its bytes.
+	///
+	/// This is a shortcut for writing the file to a new [`AsarWriter`] with
+	/// [`AsarWriter::write_file`], then finishing it with
+	/// [`AsarWriter::into_bytes`].
+	///
+	/// ## Errors
+	///
+	/// See [`AsarWriter::write_file`] and [`AsarWriter::finalize`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	/// use std::fs;
+	///
+	/// let archive = AsarWriter::single("main.js", fs::read("main.js")?, false)?;
+	/// fs::write("main.asar", archive)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn single(
+		path: impl AsRef<Path>,
+		bytes: impl AsRef<[u8]>,
+		executable: bool,
+	) -> Result<Vec<u8>> {
+		let mut writer = Self::new();
+		writer.write_file(path, bytes, executable)?;
+		writer.into_bytes()
+	}
+
+	/// Removes all the files and symbolic links from the [`AsarWriter`], so
+	/// it can be reused for another archive.
+	///
+	/// The buffer keeps its capacity, so writing a similarly sized archive
+	/// afterwards doesn't need to reallocate it. The writer keeps its
+	/// configuration, such as its [`HashAlgorithm`] and data alignment.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_file("draft.txt", b"Mostly harmless.", false)?;
+	/// writer.clear();
+	/// writer.write_file("final.txt", b"Don't Panic.", false)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn clear(&mut self) {
+		self.files.clear();
+		self.symlinks.clear();
+		self.deferred.clear();
+		self.buffer.clear();
+		self.offset = 0;
+	}
+
+	/// Adds all the files and symbolic links from an [`AsarReader`] to the
+	/// [`AsarWriter`].
+	///
+	/// Files keep whether they're executable, and their modification time, if
+	/// one was recorded. Their integrity is recalculated from their contents,
+	/// while they're copied from the reader, so each file is only read once.
+	///
+	/// See also [`AsarWriter::try_from`], for creating a new [`AsarWriter`]
+	/// from an [`AsarReader`].
+	///
+	/// ## Errors
+	///
+	///  - If a file already exists in the archive, returns an
+	///    [`Error::FileAlreadyWritten`]
 	///
 	/// ## Example
 	/// ```rust,no_run
@@ -58,9 +171,211 @@ impl AsarWriter {
 	/// # Ok::<(), asar::Error>(())
 	/// ```
 	pub fn add_from_reader(&mut self, reader: &AsarReader) -> Result<()> {
-		for (path, file) in reader.files() {
-			self.write_file(path, file.data(), false)?;
+		self.add_from_reader_with_progress(reader, |_, _| {})
+	}
+
+	/// Adds all the files and symbolic links from an [`AsarReader`] to the
+	/// [`AsarWriter`] like [`AsarWriter::add_from_reader`], calling `progress`
+	/// with the amount of files added so far and the total amount of files
+	/// after each one is added.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarWriter::add_from_reader`].
+	///
+	/// ## Example
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{AsarReader, AsarWriter};
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// let reader = AsarReader::new(&asar_file, None)?;
+	/// let mut writer = AsarWriter::new();
+	/// writer.add_from_reader_with_progress(&reader, |done, total| {
+	/// 	eprint!("\rrepacked {done}/{total} files");
+	/// })?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn add_from_reader_with_progress(
+		&mut self,
+		reader: &AsarReader,
+		mut progress: impl FnMut(usize, usize),
+	) -> Result<()> {
+		let total = reader.files().len();
+		for (idx, (path, file)) in reader.files().iter().enumerate() {
+			self.write_file_impl(
+				path,
+				file.data(),
+				file.executable(),
+				file.mtime(),
+				IntegritySource::Compute,
+			)?;
+			progress(idx + 1, total);
+		}
+		for (path, link) in reader.symlinks() {
+			self.write_symlink(path, link)?;
+		}
+		Ok(())
+	}
+
+	/// Creates an [`AsarWriter`] from a parsed [`Header`] and the archive it
+	/// was read from, keeping each file where it is and reusing its integrity,
+	/// without re-hashing anything.
+	///
+	/// `data` is the whole archive, and `begin_offset` is where its data
+	/// region starts, as returned by [`Header::read`]. This is useful for
+	/// editing the header, or replacing a few files with
+	/// [`AsarWriter::update_file`], without the cost of
+	/// [`AsarWriter::add_from_reader`].
+	///
+	/// ## Errors
+	///
+	///  - If the root of the header isn't a directory, returns an
+	///    [`Error::InvalidRoot`]
+	///  - If a file extends past the end of `data`, returns an
+	///    [`Error::Truncated`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{AsarWriter, Header};
+	/// use std::fs::{self, File};
+	///
+	/// let asar_file = fs::read("archive.asar")?;
+	/// let (header, offset) = Header::read(&mut &asar_file[..])?;
+	/// let mut writer = AsarWriter::from_existing(header, asar_file, offset)?;
+	/// writer.update_file("advice.txt", b"Don't Panic.", false)?;
+	/// writer.finalize(File::create("archive.asar")?)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn from_existing(header: Header, mut data: Vec<u8>, begin_offset: usize) -> Result<Self> {
+		header.check_root()?;
+		if begin_offset > data.len() {
+			return Err(Error::Truncated);
+		}
+		data.drain(..begin_offset);
+		let mut writer = Self::new();
+		let mut stack = vec![(PathBuf::new(), header)];
+		while let Some((path, header)) = stack.pop() {
+			match header {
+				Header::File(file) => {
+					if let Some(offset) = file.offset() {
+						let end = offset.checked_add(file.size());
+						if end.is_none_or(|end| end > data.len()) {
+							return Err(Error::Truncated);
+						}
+					}
+					writer.files.insert(path, file);
+				}
+				Header::Directory { files } => stack.extend(
+					files
+						.into_iter()
+						.map(|(name, header)| (path.join(name), header)),
+				),
+				Header::Link { link } => {
+					writer.symlinks.insert(path, link);
+				}
+			}
+		}
+		writer.offset = data.len();
+		writer.buffer = data;
+		Ok(writer)
+	}
+
+	/// Replaces the contents of a file that's already in the archive.
+	///
+	/// The new contents are appended to the end of the data region, so no
+	/// other file moves, and the old contents are left where they were, unused.
+	/// The file's modification time, if it had one, is kept.
+	///
+	/// ## Errors
+	///
+	///  - If the file isn't in the archive, returns an [`Error::FileNotFound`]
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// use asar::AsarWriter;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_file("advice.txt", b"Panic.", false)?;
+	/// writer.update_file("advice.txt", b"Don't Panic.", false)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn update_file(
+		&mut self,
+		path: impl AsRef<Path>,
+		bytes: impl AsRef<[u8]>,
+		executable: bool,
+	) -> Result<()> {
+		let path = &*self.normalize(path.as_ref());
+		let old = self
+			.files
+			.remove(path)
+			.ok_or_else(|| Error::FileNotFound(path.to_path_buf()))?;
+		let result = self.write_file_impl(
+			path,
+			bytes.as_ref(),
+			executable,
+			old.mtime(),
+			IntegritySource::Compute,
+		);
+		match result {
+			Ok(()) => {
+				self.deferred.remove(path);
+			}
+			Err(_) => {
+				self.files.insert(path.to_path_buf(), old);
+			}
+		}
+		result
+	}
+
+	/// Moves all the files and symbolic links from another [`AsarWriter`]
+	/// into this one.
+	///
+	/// The contents of `other`'s files are appended to this writer's buffer,
+	/// without re-hashing them.
+	///
+	/// ## Errors
+	///
+	///  - If any path in `other` already exists in this archive, returns an
+	///    [`Error::FileAlreadyWritten`], or an [`Error::PathConflict`] if one
+	///    is a file and the other is a symbolic link, and nothing is merged.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// use asar::AsarWriter;
+	///
+	/// let mut a = AsarWriter::new();
+	/// a.write_file("a.txt", b"Don't Panic.", false)?;
+	/// let mut b = AsarWriter::new();
+	/// b.write_file("b.txt", b"Mostly harmless.", false)?;
+	/// a.merge(b)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn merge(&mut self, other: AsarWriter) -> Result<()> {
+		let mut entries = other
+			.files
+			.keys()
+			.map(|path| (path, EntryKind::File))
+			.chain(other.symlinks.keys().map(|path| (path, EntryKind::Symlink)));
+		if let Some((path, new_kind, existing_kind)) =
+			entries.find_map(|(path, new_kind)| Some((path, new_kind, self.kind_of(path)?)))
+		{
+			return Err(conflict(path, existing_kind, new_kind));
+		}
+		self.deferred.extend(other.deferred);
+		for (path, mut file) in other.files {
+			if let Some(offset) = file.offset() {
+				file.set_location(FileLocation::offset(self.offset + offset));
+			}
+			self.files.insert(path, file);
 		}
+		self.symlinks.extend(other.symlinks);
+		self.buffer.extend_from_slice(&other.buffer);
+		self.offset += other.offset;
 		Ok(())
 	}
 
@@ -68,10 +383,19 @@ impl AsarWriter {
 	/// This appends the contents to the buffer, adds the file to the header,
 	/// and updates the offset.
 	///
+	/// The contents are copied into the buffer once, and hashed in the same
+	/// pass over them, so borrowed data, such as a
+	/// [`Cow::Borrowed`], is never copied more
+	/// than that.
+	///
 	/// ## Errors
 	///
 	///  - If the file already exists in the archive, returns an
 	///    [`Error::FileAlreadyWritten`]
+	///  - If a symbolic link has already been written to the path, returns an
+	///    [`Error::PathConflict`]
+	///  - If the path isn't valid UTF-8, and lossy paths weren't enabled with
+	///    [`AsarWriterBuilder::lossy_paths`], returns an [`Error::NonUtf8Path`]
 	///
 	/// ## Example
 	///
@@ -88,38 +412,686 @@ impl AsarWriter {
 		bytes: impl AsRef<[u8]>,
 		executable: bool,
 	) -> Result<()> {
-		self.write_file_impl(path.as_ref(), bytes.as_ref(), executable)
+		self.write_file_impl(
+			path.as_ref(),
+			bytes.as_ref(),
+			executable,
+			None,
+			IntegritySource::Compute,
+		)
+	}
+
+	/// Serializes `value` to compact JSON, and writes it to the archive as a
+	/// file, such as a `package.json`.
+	///
+	/// ## Errors
+	///
+	///  - If `value` can't be serialized, returns an [`Error::Json`]
+	///  - Otherwise, see [`AsarWriter::write_file`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	/// use serde_json::json;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_json("package.json", &json!({ "name": "app", "main": "index.js" }))?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn write_json<T: Serialize + ?Sized>(
+		&mut self,
+		path: impl AsRef<Path>,
+		value: &T,
+	) -> Result<()> {
+		let bytes = serde_json::to_vec(value)?;
+		self.write_file_impl(path.as_ref(), &bytes, false, None, IntegritySource::Compute)
+	}
+
+	/// Write a file to the archive, recording its modification time.
+	///
+	/// The modification time is stored in a `mtime` field on the file's entry
+	/// in the [`Header`], which isn't part of the asar format and is ignored by
+	/// Electron, and is restored by [`AsarReader::extract_to`].
+	///
+	/// ## Errors
+	///
+	/// See [`AsarWriter::write_file`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	/// use std::time::SystemTime;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_file_with_mtime("advice.txt", b"Don't Panic.", false, SystemTime::now())?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn write_file_with_mtime(
+		&mut self,
+		path: impl AsRef<Path>,
+		bytes: impl AsRef<[u8]>,
+		executable: bool,
+		mtime: SystemTime,
+	) -> Result<()> {
+		self.write_file_impl(
+			path.as_ref(),
+			bytes.as_ref(),
+			executable,
+			Some(mtime),
+			IntegritySource::Compute,
+		)
+	}
+
+	/// Write a file to the archive without hashing it, deferring its integrity
+	/// until the archive is finalized.
+	///
+	/// This makes writing many files cheaper up front, and lets
+	/// [`AsarWriter::finalize_parallel`] hash all of them at once, on multiple
+	/// threads. The finished archive is the same as with
+	/// [`AsarWriter::write_file`].
+	///
+	/// ## Errors
+	///
+	/// See [`AsarWriter::write_file`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_file_deferred("advice.txt", b"Don't Panic.", false)?;
+	/// let bytes = writer.into_bytes()?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn write_file_deferred(
+		&mut self,
+		path: impl AsRef<Path>,
+		bytes: impl AsRef<[u8]>,
+		executable: bool,
+	) -> Result<()> {
+		self.write_file_impl(
+			path.as_ref(),
+			bytes.as_ref(),
+			executable,
+			None,
+			IntegritySource::Defer,
+		)
+	}
+
+	/// Write a file to the archive, with the given integrity details instead
+	/// of calculating them from the contents.
+	///
+	/// This is useful when the integrity comes from elsewhere, such as a
+	/// manifest. Only the shape of the integrity is checked, not whether its
+	/// hashes match the contents.
+	///
+	/// ## Errors
+	///
+	///  - If the file already exists in the archive, returns an
+	///    [`Error::FileAlreadyWritten`]
+	///  - If the integrity's block size is zero, or it has block hashes but not
+	///    exactly one per block of `bytes`, returns an
+	///    [`Error::InvalidIntegrity`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// # use std::fs;
+	/// use asar::{AsarReader, AsarWriter};
+	/// use std::path::Path;
+	///
+	/// # let asar_file = fs::read("archive.asar")?;
+	/// let reader = AsarReader::new(&asar_file, None)?;
+	/// let file = reader.read(Path::new("advice.txt")).unwrap();
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_file_with_integrity(
+	/// 	"advice.txt",
+	/// 	file.data(),
+	/// 	false,
+	/// 	file.integrity().unwrap().clone(),
+	/// )?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn write_file_with_integrity(
+		&mut self,
+		path: impl AsRef<Path>,
+		bytes: impl AsRef<[u8]>,
+		executable: bool,
+		integrity: FileIntegrity,
+	) -> Result<()> {
+		let (path, bytes) = (path.as_ref(), bytes.as_ref());
+		crate::integrity::check_blocks(path, bytes.len(), &integrity)?;
+		self.write_file_impl(
+			path,
+			bytes,
+			executable,
+			None,
+			IntegritySource::Given(integrity),
+		)
 	}
 
+	/// Walks a directory, adding all of its files and symbolic links to the
+	/// archive, according to the given [`PackOptions`].
+	///
+	/// Paths in the archive are relative to `root`. Links in the archive are
+	/// relative to its root too, so relative symbolic links are resolved
+	/// against the directory containing them.
+	///
+	/// ## Errors
+	///
+	///  - If one of the globs in the [`PackOptions`] is invalid, returns an
+	///    [`Error::InvalidGlob`]
+	///  - If a symbolic link points outside of `root`, returns an
+	///    [`Error::SymlinkOutsideRoot`]
+	///  - If reading the directory or one of its files fails, returns an
+	///    [`Error::Io`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{writer::PackOptions, AsarWriter};
+	/// use std::fs::File;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_tree("app", PackOptions {
+	/// 	exclude_hidden: true,
+	/// 	..PackOptions::default()
+	/// })?;
+	/// writer.finalize(File::create("app.asar")?)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn write_tree(&mut self, root: impl AsRef<Path>, options: PackOptions) -> Result<()> {
+		let root = root.as_ref();
+		let executable = options.executable.as_deref().map(parse_glob).transpose()?;
+		let paths = walk_tree(root, &options)?;
+
+		let hasher = self.hasher;
+		let prepare = |path: &PathBuf, hash: bool| -> Result<PackEntry> {
+			let stripped_path = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+			if path.is_symlink() {
+				let link = tree_link(root, path, &stripped_path)?;
+				return Ok(PackEntry::Symlink {
+					path: stripped_path,
+					link,
+				});
+			}
+
+			let bytes = fs::read(path)?;
+			let executable = is_tree_executable(executable.as_ref(), path, &stripped_path);
+			let mtime = if options.record_mtimes {
+				Some(fs::metadata(path)?.modified()?)
+			} else {
+				None
+			};
+			let integrity = hash.then(|| {
+				let (hash, blocks) = hasher.hash_with_blocks(DEFAULT_BLOCK_SIZE, &bytes);
+				FileIntegrity::new(hasher, hash, DEFAULT_BLOCK_SIZE, blocks)
+			});
+			Ok(PackEntry::File {
+				path: stripped_path,
+				bytes,
+				executable,
+				mtime,
+				integrity,
+			})
+		};
+
+		match options.jobs {
+			// Each file is read and written before the next, so only one file
+			// is held in memory at a time.
+			None => {
+				for path in &paths {
+					let entry = prepare(path, false)?;
+					self.add_pack_entry(entry)?;
+				}
+			}
+			// Files are read and hashed in parallel, then written in the same
+			// order as above, so the archive is identical.
+			Some(jobs) => {
+				// Compressed files are hashed after they're compressed, which
+				// happens as they're written.
+				#[cfg(feature = "compression")]
+				let hash = self.compression.is_none();
+				#[cfg(not(feature = "compression"))]
+				let hash = true;
+				let pool = rayon::ThreadPoolBuilder::new()
+					.num_threads(jobs)
+					.build()
+					.map_err(std::io::Error::other)?;
+				let entries = pool.install(|| {
+					paths
+						.par_iter()
+						.map(|path| prepare(path, hash))
+						.collect::<Result<Vec<_>>>()
+				})?;
+				for entry in entries {
+					self.add_pack_entry(entry)?;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Works out what [`AsarWriter::write_tree`] would add to the archive with
+	/// the same [`PackOptions`], without reading or hashing any files.
+	///
+	/// The files are found with the same rules, and their sizes come from the
+	/// file system, so the [`PackPlan`] lists the same files and links, and
+	/// gives the size of the archive the tree would be packed to on its own.
+	/// Files already written to this writer aren't included. With
+	/// compression enabled, the size is that of the archive without any
+	/// compression, as the files would need reading to compress them.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarWriter::write_tree`]. Files which can't be read, but whose
+	/// metadata can, don't cause an error here.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::{writer::PackOptions, AsarWriter};
+	///
+	/// let plan = AsarWriter::new().plan_tree("app", &PackOptions::default())?;
+	/// for (path, size) in &plan.files {
+	/// 	println!("{} ({size} bytes)", path.display());
+	/// }
+	/// println!("app.asar would be {} bytes", plan.size);
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn plan_tree(&self, root: impl AsRef<Path>, options: &PackOptions) -> Result<PackPlan> {
+		let root = root.as_ref();
+		let executable = options.executable.as_deref().map(parse_glob).transpose()?;
+		let paths = walk_tree(root, options)?;
+		// A writer with the same options, but no files, which the tree is
+		// "written" to with only its metadata.
+		let mut planned = Self {
+			files: BTreeMap::new(),
+			symlinks: BTreeMap::new(),
+			deferred: BTreeSet::new(),
+			buffer: Vec::new(),
+			offset: 0,
+			hasher: self.hasher,
+			data_alignment: self.data_alignment,
+			pretty_header: self.pretty_header,
+			lossy_paths: self.lossy_paths,
+			#[cfg(feature = "compression")]
+			compression: None,
+			#[cfg(feature = "normalize-unicode")]
+			normalize_unicode: self.normalize_unicode,
+		};
+		for path in &paths {
+			let stripped_path = path.strip_prefix(root).unwrap_or(path);
+			if path.is_symlink() {
+				let link = tree_link(root, path, stripped_path)?;
+				planned.write_symlink(stripped_path, link)?;
+				continue;
+			}
+			let metadata = fs::metadata(path)?;
+			let size = metadata.len() as usize;
+			let executable = is_tree_executable(executable.as_ref(), path, stripped_path);
+			let mut file = File::new(
+				FileLocation::Offset {
+					offset: planned.offset,
+				},
+				size,
+				executable,
+				Some(placeholder_integrity(self.hasher, size)),
+			);
+			if options.record_mtimes {
+				file.set_mtime(metadata.modified()?);
+			}
+			let stripped_path = planned.normalize(stripped_path).into_owned();
+			planned.check_new_file(&stripped_path)?;
+			planned.offset += size;
+			planned.files.insert(stripped_path, file);
+		}
+		planned.plan()
+	}
+
+	/// Works out what finalizing the archive would write, without finalizing
+	/// it, or hashing the files written with
+	/// [`AsarWriter::write_file_deferred`].
+	///
+	/// ## Errors
+	///
+	/// See [`AsarWriter::finalize`]; nothing is written, so this can't fail
+	/// with an [`Error::Io`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_file("advice.txt", b"Don't Panic.", false)?;
+	/// assert_eq!(writer.plan()?.files.len(), 1);
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn plan(&self) -> Result<PackPlan> {
+		let mut files = self.files.clone();
+		for path in &self.deferred {
+			if let Some(file) = files.get_mut(path) {
+				file.set_integrity(placeholder_integrity(self.hasher, file.size()));
+			}
+		}
+		let mut planned_files = files
+			.iter()
+			.map(|(path, file)| (path.clone(), file.size(), file.offset()))
+			.collect::<Vec<_>>();
+		planned_files.sort_by_key(|(_, _, offset)| *offset);
+		let json = self.header_json(files, self.symlinks.clone())?;
+		Ok(PackPlan {
+			files: planned_files
+				.into_iter()
+				.map(|(path, size, _)| (path, size))
+				.collect(),
+			symlinks: self
+				.symlinks
+				.iter()
+				.map(|(path, link)| (path.clone(), link.clone()))
+				.collect(),
+			size: data_start(json.len(), self.data_alignment) + self.offset,
+		})
+	}
+
+	fn add_pack_entry(&mut self, entry: PackEntry) -> Result<()> {
+		match entry {
+			PackEntry::Symlink { path, link } => self.write_symlink(path, link),
+			PackEntry::File {
+				path,
+				bytes,
+				executable,
+				mtime,
+				integrity,
+			} => self.write_file_impl(
+				&path,
+				&bytes,
+				executable,
+				mtime,
+				integrity.map_or(IntegritySource::Compute, IntegritySource::Given),
+			),
+		}
+	}
+
+	/// Write a symbolic link to the archive, pointing to `link`, which is
+	/// relative to the root of the archive.
+	///
+	/// ## Errors
+	///
+	///  - If a file has already been written to the path, returns an
+	///    [`Error::PathConflict`]. An existing symbolic link is replaced.
+	///  - If the path or the link isn't valid UTF-8, and lossy paths weren't
+	///    enabled with [`AsarWriterBuilder::lossy_paths`], returns an
+	///    [`Error::NonUtf8Path`]
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_file("advice.txt", b"Don't Panic.", false)?;
+	/// writer.write_symlink("guide.txt", "advice.txt")?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
 	pub fn write_symlink(&mut self, path: impl AsRef<Path>, link: impl AsRef<Path>) -> Result<()> {
-		self.symlinks
-			.insert(path.as_ref().to_path_buf(), link.as_ref().to_path_buf());
+		let (path, link) = (self.normalize(path.as_ref()), self.normalize(link.as_ref()));
+		if self.files.contains_key(&*path) {
+			return Err(conflict(&path, EntryKind::File, EntryKind::Symlink));
+		}
+		self.check_utf8(&path)?;
+		self.check_utf8(&link)?;
+		self.symlinks.insert(
+			path.to_path_buf(),
+			PathBuf::from(link.to_string_lossy().into_owned()),
+		);
 		Ok(())
 	}
 
-	fn write_file_impl(&mut self, path: &Path, bytes: &[u8], executable: bool) -> Result<()> {
+	/// The kind of entry already written to `path`, if there is one.
+	fn kind_of(&self, path: &Path) -> Option<EntryKind> {
 		if self.files.contains_key(path) {
-			return Err(Error::FileAlreadyWritten(path.to_path_buf()));
+			Some(EntryKind::File)
+		} else if self.symlinks.contains_key(path) {
+			Some(EntryKind::Symlink)
+		} else {
+			None
+		}
+	}
+
+	/// Checks that a file can be written to `path`, which has already been
+	/// normalized.
+	fn check_new_file(&self, path: &Path) -> Result<()> {
+		if let Some(existing_kind) = self.kind_of(path) {
+			return Err(conflict(path, existing_kind, EntryKind::File));
+		}
+		self.check_utf8(path)
+	}
+
+	/// Checks that a path is valid UTF-8, as the header can't store anything
+	/// else, unless paths are allowed to be converted lossily.
+	fn check_utf8(&self, path: &Path) -> Result<()> {
+		if !self.lossy_paths && path.to_str().is_none() {
+			return Err(Error::NonUtf8Path(path.to_path_buf()));
 		}
-		let file = File::new(
+		Ok(())
+	}
+
+	/// Normalizes a path to Unicode NFC, if enabled with
+	/// [`AsarWriterBuilder::normalize_unicode`].
+	#[cfg(feature = "normalize-unicode")]
+	fn normalize<'p>(&self, path: &'p Path) -> Cow<'p, Path> {
+		if self.normalize_unicode {
+			crate::unicode::nfc_path(path)
+		} else {
+			Cow::Borrowed(path)
+		}
+	}
+
+	#[cfg(not(feature = "normalize-unicode"))]
+	const fn normalize<'p>(&self, path: &'p Path) -> Cow<'p, Path> {
+		Cow::Borrowed(path)
+	}
+
+	fn write_file_impl(
+		&mut self,
+		path: &Path,
+		bytes: &[u8],
+		executable: bool,
+		mtime: Option<SystemTime>,
+		integrity: IntegritySource,
+	) -> Result<()> {
+		let path = &*self.normalize(path);
+		self.check_new_file(path)?;
+		// Files with a given integrity aren't compressed, as it'd be of their
+		// uncompressed contents.
+		#[cfg(feature = "compression")]
+		let compressed = match (self.compression, &integrity) {
+			(Some(method), IntegritySource::Compute | IntegritySource::Defer) => {
+				Some((crate::compression::compress(method, bytes)?, Compression {
+					method,
+					original_size: bytes.len(),
+				}))
+			}
+			_ => None,
+		};
+		#[cfg(feature = "compression")]
+		let bytes = compressed
+			.as_ref()
+			.map_or(bytes, |(data, _)| data.as_slice());
+		// The contents are copied into the buffer exactly once, and when the
+		// integrity needs calculating, that happens in the same pass as the copy.
+		let integrity = match integrity {
+			IntegritySource::Given(integrity) => {
+				self.buffer.extend_from_slice(bytes);
+				Some(integrity)
+			}
+			IntegritySource::Compute => {
+				let (hash, blocks) =
+					self.hasher
+						.copy_with_blocks(DEFAULT_BLOCK_SIZE, bytes, &mut self.buffer);
+				Some(FileIntegrity::new(
+					self.hasher,
+					hash,
+					DEFAULT_BLOCK_SIZE,
+					blocks,
+				))
+			}
+			IntegritySource::Defer => {
+				self.buffer.extend_from_slice(bytes);
+				self.deferred.insert(path.to_path_buf());
+				None
+			}
+		};
+		let mut file = File::new(
 			FileLocation::Offset {
 				offset: self.offset,
 			},
 			bytes.len(),
 			executable,
-			Some(FileIntegrity::new(
-				self.hasher,
-				self.hasher.hash(bytes),
-				BLOCK_SIZE,
-				self.hasher.hash_blocks(BLOCK_SIZE, bytes),
-			)),
+			integrity,
 		);
-		self.buffer.extend_from_slice(bytes);
+		if let Some(mtime) = mtime {
+			file.set_mtime(mtime);
+		}
+		#[cfg(feature = "compression")]
+		if let Some((_, compression)) = compressed {
+			file.set_compression(compression);
+		}
 		self.offset += bytes.len();
 		self.files.insert(path.to_path_buf(), file);
 		Ok(())
 	}
 
+	/// Finalizes the archive into the file at `path`, replacing it if it
+	/// exists, and returns the amount of bytes written.
+	///
+	/// The archive is first written to a temporary file next to `path` (such as
+	/// `app.asar.tmp` for `app.asar`), which is then renamed over `path` once
+	/// it's complete. As renaming is atomic, a crash while writing never leaves
+	/// a partially written archive at `path`.
+	///
+	/// If the temporary file can't be renamed because it's on a different file
+	/// system to `path`, which can happen with some mounts, it's copied over
+	/// `path` instead, which isn't atomic.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarWriter::finalize`]. The temporary file is removed if writing
+	/// fails.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_file("advice.txt", b"Don't Panic.", false)?;
+	/// writer.finalize_to_path("app.asar")?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn finalize_to_path(self, path: impl AsRef<Path>) -> Result<usize> {
+		let path = path.as_ref();
+		let mut tmp_path = path.as_os_str().to_owned();
+		tmp_path.push(".tmp");
+		let tmp_path = PathBuf::from(tmp_path);
+
+		let write = || -> Result<usize> {
+			let mut out = BufWriter::new(fs::File::create(&tmp_path)?);
+			let written = self.finalize(&mut out)?;
+			out.into_inner()
+				.map_err(|err| err.into_error())?
+				.sync_all()?;
+			Ok(written)
+		};
+		let written = match write() {
+			Ok(written) => written,
+			Err(err) => {
+				let _ = fs::remove_file(&tmp_path);
+				return Err(err);
+			}
+		};
+		match fs::rename(&tmp_path, path) {
+			Ok(()) => {}
+			Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+				let copied = fs::copy(&tmp_path, path);
+				let _ = fs::remove_file(&tmp_path);
+				copied?;
+			}
+			Err(err) => {
+				let _ = fs::remove_file(&tmp_path);
+				return Err(err.into());
+			}
+		}
+		Ok(written)
+	}
+
+	/// Finalizes the archive into a [`Vec`], returning it.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarWriter::finalize`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_file("advice.txt", b"Don't Panic.", false)?;
+	/// let archive = writer.into_bytes()?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn into_bytes(self) -> Result<Vec<u8>> {
+		let mut bytes = Vec::with_capacity(self.buffer.len());
+		self.finalize(&mut bytes)?;
+		Ok(bytes)
+	}
+
+	/// Summarizes the files that have been written to the archive so far,
+	/// without finalizing it.
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// writer.write_tree("app", Default::default())?;
+	/// let stats = writer.stats();
+	/// if stats
+	/// 	.by_extension
+	/// 	.get("node")
+	/// 	.is_some_and(|node| node.largest > 50_000_000)
+	/// {
+	/// 	panic!("native modules must be under 50 MB");
+	/// }
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn stats(&self) -> WriterStats {
+		let mut stats = WriterStats {
+			symlinks: self.symlinks.len(),
+			..WriterStats::default()
+		};
+		for (path, file) in &self.files {
+			let extension = path
+				.extension()
+				.map(|extension| extension.to_string_lossy().into_owned())
+				.unwrap_or_default();
+			let extension_stats = stats.by_extension.entry(extension).or_default();
+			extension_stats.files += 1;
+			extension_stats.bytes += file.size();
+			extension_stats.largest = extension_stats.largest.max(file.size());
+			stats.files += 1;
+			stats.bytes += file.size();
+		}
+		stats
+	}
+
 	/// Finalizes the archive, writing the [`Header`] and the files to the
 	/// writer.
 	///
@@ -127,6 +1099,23 @@ impl AsarWriter {
 	///
 	/// Returns the amount of bytes written.
 	///
+	/// The archive is laid out the same way as `@electron/asar` lays it out,
+	/// as two Chromium pickles followed by the file data:
+	///
+	///  - `u32` 4, the size of the first pickle's payload
+	///  - `u32` the size of the second pickle, which is the padded JSON plus 8
+	///  - `u32` the size of the second pickle's payload, the padded JSON plus 4
+	///  - `u32` the length of the JSON
+	///  - the JSON, padded with zeroes to a multiple of the data alignment
+	///  - the file data, starting right after the padding
+	///
+	/// Nothing else is written between the JSON and the data, so the data
+	/// always starts at the second `u32` plus 8.
+	///
+	/// Files written with [`AsarWriter::write_file_deferred`] are hashed
+	/// first, one after another. Use [`AsarWriter::finalize_parallel`] to hash
+	/// them on multiple threads instead.
+	///
 	/// ## Errors
 	///
 	///  - If writing fails, an [std::io::Error] is returned.
@@ -147,24 +1136,18 @@ impl AsarWriter {
 	/// writer.finalize(&mut file)?;
 	/// # Ok::<(), asar::Error>(())
 	/// ```
-	pub fn finalize<FinalWriter>(self, mut final_writer: FinalWriter) -> Result<usize>
+	pub fn finalize<FinalWriter>(mut self, mut final_writer: FinalWriter) -> Result<usize>
 	where
 		FinalWriter: Write,
 	{
-		let mut header = Header::new();
-		for (path, file) in self.files {
-			let path = path_to_reverse_components(&path)?;
-			recursive_add_to_header(path, Header::File(file), &mut header);
-		}
-		for (path, link) in self.symlinks {
-			let path = path_to_reverse_components(&path)?;
-			recursive_add_to_header(path, Header::Link { link }, &mut header);
-		}
+		self.hash_deferred(false);
+		let files = std::mem::take(&mut self.files);
+		let symlinks = std::mem::take(&mut self.symlinks);
 		let mut written = 0;
-		let mut json = serde_json::to_string(&header)?.into_bytes();
+		let mut json = self.header_json(files, symlinks)?;
 
 		let json_size = json.len() as u32;
-		let aligned_json_size = json_size + (4 - (json_size % 4)) % 4;
+		let aligned_json_size = (data_start(json.len(), self.data_alignment) - 16) as u32;
 		json.resize(aligned_json_size as usize, 0);
 
 		final_writer.write_u32::<LittleEndian>(4)?;
@@ -182,20 +1165,616 @@ impl AsarWriter {
 		final_writer.flush()?;
 		Ok(written)
 	}
+
+	/// Finalizes the archive like [`AsarWriter::finalize`], but hashes the
+	/// files written with [`AsarWriter::write_file_deferred`] in parallel,
+	/// on rayon's global thread pool.
+	///
+	/// ## Errors
+	///
+	/// See [`AsarWriter::finalize`].
+	///
+	/// ## Example
+	///
+	/// ```rust,no_run
+	/// use asar::AsarWriter;
+	/// use std::fs::File;
+	///
+	/// let mut writer = AsarWriter::new();
+	/// for idx in 0..1000 {
+	/// 	writer.write_file_deferred(format!("{idx}.txt"), idx.to_string(), false)?;
+	/// }
+	/// writer.finalize_parallel(File::create("archive.asar")?)?;
+	/// # Ok::<(), asar::Error>(())
+	/// ```
+	pub fn finalize_parallel<FinalWriter>(mut self, final_writer: FinalWriter) -> Result<usize>
+	where
+		FinalWriter: Write,
+	{
+		self.hash_deferred(true);
+		self.finalize(final_writer)
+	}
+
+	/// Serializes the [`Header`] listing `files` and `symlinks`, without any
+	/// padding.
+	fn header_json(
+		&self,
+		files: BTreeMap<PathBuf, File>,
+		symlinks: BTreeMap<PathBuf, PathBuf>,
+	) -> Result<Vec<u8>> {
+		let mut header = Header::new();
+		for (path, file) in files {
+			let path = path_to_reverse_components(&path)?;
+			recursive_add_to_header(path, Header::File(file), &mut header);
+		}
+		for (path, link) in symlinks {
+			let path = path_to_reverse_components(&path)?;
+			recursive_add_to_header(path, Header::Link { link }, &mut header);
+		}
+		Ok(if self.pretty_header {
+			serde_json::to_string_pretty(&header)?
+		} else {
+			serde_json::to_string(&header)?
+		}
+		.into_bytes())
+	}
+
+	/// Calculates the integrity of each file written with
+	/// [`AsarWriter::write_file_deferred`], from its contents in the buffer.
+	fn hash_deferred(&mut self, parallel: bool) {
+		let (hasher, buffer) = (self.hasher, &self.buffer);
+		let hash = |file: &mut File| {
+			let offset = file
+				.offset()
+				.expect("deferred files should be in the buffer");
+			let bytes = &buffer[offset..offset + file.size()];
+			let (hash, blocks) = hasher.hash_with_blocks(DEFAULT_BLOCK_SIZE, bytes);
+			file.set_integrity(FileIntegrity::new(hasher, hash, DEFAULT_BLOCK_SIZE, blocks));
+		};
+		let mut files = self
+			.files
+			.iter_mut()
+			.filter(|(path, _)| self.deferred.contains(*path))
+			.map(|(_, file)| file)
+			.collect::<Vec<_>>();
+		if parallel {
+			files.par_iter_mut().for_each(|file| hash(file));
+		} else {
+			files.iter_mut().for_each(|file| hash(file));
+		}
+		self.deferred.clear();
+	}
+}
+
+impl Default for AsarWriter {
+	/// Creates a new [`AsarWriter`], with an empty buffer and the default
+	/// [`HashAlgorithm`]
+	fn default() -> Self {
+		Self {
+			files: BTreeMap::new(),
+			symlinks: BTreeMap::new(),
+			deferred: BTreeSet::new(),
+			offset: 0,
+			buffer: Vec::new(),
+			hasher: HashAlgorithm::Sha256,
+			data_alignment: DEFAULT_DATA_ALIGNMENT,
+			pretty_header: false,
+			lossy_paths: false,
+			#[cfg(feature = "compression")]
+			compression: None,
+			#[cfg(feature = "normalize-unicode")]
+			normalize_unicode: false,
+		}
+	}
+}
+
+/// The alignment of the data region when none is configured, which is the
+/// minimum alignment of the header.
+const DEFAULT_DATA_ALIGNMENT: usize = 4;
+
+/// A builder for an [`AsarWriter`], created with [`AsarWriter::builder`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AsarWriterBuilder {
+	hasher: HashAlgorithm,
+	data_alignment: usize,
+	pretty_header: bool,
+	lossy_paths: bool,
+	#[cfg(feature = "compression")]
+	compression: Option<CompressionMethod>,
+	#[cfg(feature = "normalize-unicode")]
+	normalize_unicode: bool,
+}
+
+impl AsarWriterBuilder {
+	/// Creates a new [`AsarWriterBuilder`], with the default options.
+	#[inline]
+	pub const fn new() -> Self {
+		Self {
+			hasher: HashAlgorithm::Sha256,
+			data_alignment: DEFAULT_DATA_ALIGNMENT,
+			pretty_header: false,
+			lossy_paths: false,
+			#[cfg(feature = "compression")]
+			compression: None,
+			#[cfg(feature = "normalize-unicode")]
+			normalize_unicode: false,
+		}
+	}
+
+	/// Sets the [`HashAlgorithm`] used to calculate the integrity of files.
+	#[inline]
+	pub const fn algorithm(mut self, hasher: HashAlgorithm) -> Self {
+		self.hasher = hasher;
+		self
+	}
+
+	/// Pads the header, so that the data region — and so the first file —
+	/// starts at an absolute offset that's a multiple of `alignment`.
+	///
+	/// The header is always aligned to 4 bytes, so alignments below that have
+	/// no effect.
+	///
+	/// ## Panics
+	///
+	///  - If `alignment` isn't a power of two.
+	#[inline]
+	pub const fn data_alignment(mut self, alignment: usize) -> Self {
+		assert!(
+			alignment.is_power_of_two(),
+			"data alignment must be a power of two"
+		);
+		self.data_alignment = if alignment < DEFAULT_DATA_ALIGNMENT {
+			DEFAULT_DATA_ALIGNMENT
+		} else {
+			alignment
+		};
+		self
+	}
+
+	/// Writes the header JSON pretty-printed, which makes it easier to read
+	/// when debugging, but makes the archive larger.
+	///
+	/// This is off by default. Note that this changes the bytes of the header,
+	/// and so its hash, which Electron may check.
+	#[inline]
+	pub const fn pretty_header(mut self, pretty_header: bool) -> Self {
+		self.pretty_header = pretty_header;
+		self
+	}
+
+	/// Converts paths which aren't valid UTF-8 lossily, replacing invalid
+	/// sequences with `�`, rather than failing with an
+	/// [`Error::NonUtf8Path`].
+	///
+	/// This is off by default, as the converted paths won't match the
+	/// original ones.
+	#[inline]
+	pub const fn lossy_paths(mut self, lossy_paths: bool) -> Self {
+		self.lossy_paths = lossy_paths;
+		self
+	}
+
+	/// Compresses the contents of each file with `method`, recording it in the
+	/// header so that they can be decompressed when they're read.
+	///
+	/// **Archives written with this can't be read by Electron**, which will
+	/// see each file's compressed contents. They can only be read by this
+	/// crate, with the `compression` feature enabled.
+	///
+	/// Files written with [`AsarWriter::write_file_with_integrity`] aren't
+	/// compressed, as their integrity is of their uncompressed contents.
+	#[inline]
+	#[cfg(feature = "compression")]
+	pub const fn compress(mut self, method: CompressionMethod) -> Self {
+		self.compression = Some(method);
+		self
+	}
+
+	/// Normalizes every path written to the archive, including the targets of
+	/// symbolic links, to Unicode Normalization Form C.
+	///
+	/// macOS stores file names decomposed (NFD), while most other systems
+	/// store them composed (NFC), so the same name packed on each can end up
+	/// as different bytes in the header: two files which look the same can
+	/// both be written, or a file can fail to be found when looking it up on
+	/// another system. Normalizing paths gives the same archive regardless of
+	/// where it was packed. Look paths up with
+	/// [`AsarReader::read_normalized`](crate::reader::AsarReader::read_normalized)
+	/// to match them however they were written.
+	///
+	/// This is off by default, as it changes the bytes of the paths stored.
+	#[inline]
+	#[cfg(feature = "normalize-unicode")]
+	pub const fn normalize_unicode(mut self, normalize_unicode: bool) -> Self {
+		self.normalize_unicode = normalize_unicode;
+		self
+	}
+
+	/// Creates the [`AsarWriter`].
+	#[inline]
+	pub const fn build(self) -> AsarWriter {
+		let mut writer = AsarWriter::new_with_algorithm(self.hasher);
+		writer.data_alignment = self.data_alignment;
+		writer.pretty_header = self.pretty_header;
+		writer.lossy_paths = self.lossy_paths;
+		#[cfg(feature = "compression")]
+		{
+			writer.compression = self.compression;
+		}
+		#[cfg(feature = "normalize-unicode")]
+		{
+			writer.normalize_unicode = self.normalize_unicode;
+		}
+		writer
+	}
+}
+
+impl Default for AsarWriterBuilder {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Creates an [`AsarWriter`] containing all the files and symbolic links from
+/// an [`AsarReader`], so that they can be modified and written back out.
+///
+/// See [`AsarWriter::add_from_reader`].
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use asar::{AsarReader, AsarWriter};
+/// use std::fs::{self, File};
+///
+/// let asar_file = fs::read("archive.asar")?;
+/// let mut writer = AsarWriter::try_from(&AsarReader::new(&asar_file, None)?)?;
+/// writer.write_file("advice.txt", b"Don't Panic.", false)?;
+/// writer.finalize(File::create("archive.asar")?)?;
+/// # Ok::<(), asar::Error>(())
+/// ```
+impl TryFrom<&AsarReader<'_>> for AsarWriter {
+	type Error = Error;
+
+	fn try_from(reader: &AsarReader<'_>) -> Result<Self> {
+		let mut writer = Self::new();
+		writer.add_from_reader(reader)?;
+		Ok(writer)
+	}
+}
+
+impl TryFrom<AsarReader<'_>> for AsarWriter {
+	type Error = Error;
+
+	#[inline]
+	fn try_from(reader: AsarReader<'_>) -> Result<Self> {
+		Self::try_from(&reader)
+	}
+}
+
+/// Writes each file from an iterator of paths and contents to the
+/// [`AsarWriter`], as non-executable files.
+///
+/// ## Panics
+///
+///  - If a path is written more than once, either by the iterator or before it,
+///    as the contents of the first file have already been written. Use
+///    [`AsarWriter::write_file`] to handle this instead.
+///  - If a path isn't valid UTF-8, and lossy paths weren't enabled with
+///    [`AsarWriterBuilder::lossy_paths`].
+///
+/// ## Example
+///
+/// ```rust
+/// use asar::AsarWriter;
+/// use std::path::PathBuf;
+///
+/// let mut writer = AsarWriter::new();
+/// writer.extend([(PathBuf::from("advice.txt"), b"Don't Panic.".to_vec())]);
+/// ```
+impl Extend<(PathBuf, Vec<u8>)> for AsarWriter {
+	fn extend<T: IntoIterator<Item = (PathBuf, Vec<u8>)>>(&mut self, iter: T) {
+		for (path, bytes) in iter {
+			if let Err(err) =
+				self.write_file_impl(&path, &bytes, false, None, IntegritySource::Compute)
+			{
+				panic!("failed to write file: {err}");
+			}
+		}
+	}
+}
+
+/// Creates an [`AsarWriter`] from an iterator of paths and contents, with each
+/// file being non-executable.
+///
+/// ## Panics
+///
+/// See the [`Extend`] implementation. Paths can't be written by anything
+/// before the iterator here, so this only panics on paths repeated by the
+/// iterator, or on paths which aren't valid UTF-8.
+///
+/// ## Example
+///
+/// ```rust
+/// use asar::AsarWriter;
+/// use std::{collections::BTreeMap, path::PathBuf};
+///
+/// let files = BTreeMap::from([
+/// 	(PathBuf::from("advice.txt"), b"Don't Panic.".to_vec()),
+/// 	(
+/// 		PathBuf::from("towel.txt"),
+/// 		b"Always know where it is.".to_vec(),
+/// 	),
+/// ]);
+/// let writer: AsarWriter = files.into_iter().collect();
+/// ```
+impl FromIterator<(PathBuf, Vec<u8>)> for AsarWriter {
+	fn from_iter<T: IntoIterator<Item = (PathBuf, Vec<u8>)>>(iter: T) -> Self {
+		let mut writer = Self::new();
+		writer.extend(iter);
+		writer
+	}
+}
+
+/// A summary of the files written to an [`AsarWriter`], as returned by
+/// [`AsarWriter::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriterStats {
+	/// The amount of files.
+	pub files: usize,
+	/// The total size of all files, in bytes.
+	pub bytes: usize,
+	/// The amount of symbolic links.
+	pub symlinks: usize,
+	/// The files, grouped by their extension, without the leading `.`.
+	///
+	/// Files without an extension are grouped under an empty string.
+	pub by_extension: BTreeMap<String, ExtensionStats>,
+}
+
+/// A summary of the files with a specific extension, in [`WriterStats`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ExtensionStats {
+	/// The amount of files.
+	pub files: usize,
+	/// The total size of the files, in bytes.
+	pub bytes: usize,
+	/// The size of the largest file, in bytes.
+	pub largest: usize,
+}
+
+/// What an archive would contain, as worked out by [`AsarWriter::plan_tree`]
+/// or [`AsarWriter::plan`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackPlan {
+	/// The paths and sizes of the files, in the order their data is written
+	/// in.
+	pub files: Vec<(PathBuf, usize)>,
+	/// The paths of the symbolic links, and the paths they point to, in order
+	/// of their paths.
+	pub symlinks: Vec<(PathBuf, PathBuf)>,
+	/// The size of the finalized archive, in bytes, including its header.
+	pub size: usize,
+}
+
+/// A file or symbolic link read by [`AsarWriter::write_tree`], ready to be
+/// written to the archive.
+enum PackEntry {
+	Symlink {
+		path: PathBuf,
+		link: PathBuf,
+	},
+	File {
+		path: PathBuf,
+		bytes: Vec<u8>,
+		executable: bool,
+		mtime: Option<SystemTime>,
+		integrity: Option<FileIntegrity>,
+	},
+}
+
+/// Where [`AsarWriter::write_file_impl`] gets a file's integrity from.
+enum IntegritySource {
+	/// Hash the contents while copying them into the buffer.
+	Compute,
+	/// Hash the contents when the archive is finalized.
+	Defer,
+	/// Use the given integrity, without hashing anything.
+	Given(FileIntegrity),
+}
+
+/// The order [`AsarWriter::write_tree`] writes files to the archive's data
+/// in, after the files listed in [`PackOptions::ordering`].
+///
+/// The header lists files by name either way; this only changes where their
+/// data is.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum SortOrder {
+	/// Sort files by their paths, like `@electron/asar` does.
+	#[default]
+	Name,
+	/// Sort files by their size, smallest first.
+	Size,
+	/// Sort files by their extension, with files without an extension first.
+	Extension,
+}
+
+/// Options for [`AsarWriter::write_tree`], controlling which files from the
+/// directory are added to the archive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackOptions {
+	/// Do not pack files matching this glob.
+	pub unpack: Option<String>,
+	/// Do not pack files in directories matching this glob.
+	pub unpack_dir: Option<String>,
+	/// Mark files matching this glob as executable, regardless of their
+	/// permissions on the file system.
+	pub executable: Option<String>,
+	/// Do not pack hidden files, whose names start with a `.`, or files inside
+	/// hidden directories.
+	pub exclude_hidden: bool,
+	/// Files to place at the start of the archive's data, in order.
+	///
+	/// See [`parse_ordering`](crate::ordering::parse_ordering).
+	pub ordering: Vec<OrderingEntry>,
+	/// The order to write the rest of the files in. Files with the same size
+	/// or extension are sorted by their paths.
+	pub sort: SortOrder,
+	/// Record the modification time of each file in the archive.
+	///
+	/// See [`AsarWriter::write_file_with_mtime`].
+	pub record_mtimes: bool,
+	/// Follow symbolic links to directories, packing their contents as if
+	/// they were in the directory being packed. Otherwise, they're skipped.
+	///
+	/// Symbolic links to files are always packed as symbolic links, even when
+	/// this is set; there's no option to pack the files they point to instead.
+	/// `root` itself is always followed if it's a symbolic link.
+	pub follow_symlinks: bool,
+	/// Read and hash files on this many threads, or as many as there are CPUs
+	/// if this is `Some(0)`. The archive is the same as when packing on a
+	/// single thread.
+	///
+	/// When this is `None`, files are packed one at a time, on the current
+	/// thread. Otherwise, all the files are read into memory before any are
+	/// added to the archive.
+	pub jobs: Option<usize>,
+}
+
+/// The error for writing an entry of `new_kind` where one of `existing_kind`
+/// has already been written.
+fn conflict(path: &Path, existing_kind: EntryKind, new_kind: EntryKind) -> Error {
+	if existing_kind == new_kind {
+		Error::FileAlreadyWritten(path.to_path_buf())
+	} else {
+		Error::PathConflict {
+			path: path.to_path_buf(),
+			existing_kind,
+			new_kind,
+		}
+	}
 }
 
-impl Default for AsarWriter {
-	/// Creates a new [`AsarWriter`], with an empty buffer and the default
-	/// [`HashAlgorithm`]
-	fn default() -> Self {
-		Self {
-			files: BTreeMap::new(),
-			symlinks: BTreeMap::new(),
-			offset: 0,
-			buffer: Vec::new(),
-			hasher: HashAlgorithm::Sha256,
+fn parse_glob(glob: &str) -> Result<Glob<'_>> {
+	Glob::new(glob).map_err(|err| Error::InvalidGlob {
+		glob: glob.to_string(),
+		err: err.to_string(),
+	})
+}
+
+/// Walks the directory at `root`, returning the paths of the files and
+/// symbolic links that [`AsarWriter::write_tree`] packs, in the order their
+/// data is written in.
+fn walk_tree(root: &Path, options: &PackOptions) -> Result<Vec<PathBuf>> {
+	let unpack = options.unpack.as_deref().map(parse_glob).transpose()?;
+	let unpack_dir = options.unpack_dir.as_deref().map(parse_glob).transpose()?;
+	let mut paths = Vec::new();
+	// Entries are walked in order of their names, like `@electron/asar`
+	// does, so that packing the same tree always gives the same archive.
+	let walk = WalkDir::new(root)
+		.follow_root_links(true)
+		.follow_links(options.follow_symlinks)
+		.sort_by_file_name();
+	for entry in walk {
+		let entry = entry.map_err(std::io::Error::from)?;
+		let path = entry.path();
+		if !path.is_file() {
+			continue;
+		}
+		let stripped_path = path
+			.strip_prefix(root)
+			.expect("walked path should be inside the root");
+		// Files inside hidden directories are hidden too.
+		let hidden = stripped_path
+			.components()
+			.any(|component| component.as_os_str().to_string_lossy().starts_with('.'));
+		if options.exclude_hidden && hidden {
+			continue;
+		}
+		if let (Some(parent), Some(unpack_dir_glob)) = (stripped_path.parent(), &unpack_dir) {
+			if unpack_dir_glob.is_match(parent) {
+				continue;
+			}
+		}
+		if let Some(unpack_glob) = &unpack {
+			if unpack_glob.is_match(stripped_path) {
+				continue;
+			}
+		}
+		paths.push(entry.into_path());
+	}
+
+	// Both sorts are stable, so files with the same key keep the order
+	// they were walked in, and the sort order only applies to files which
+	// aren't in the ordering.
+	match options.sort {
+		SortOrder::Name => {}
+		SortOrder::Size => paths.sort_by_cached_key(|path| {
+			fs::symlink_metadata(path).map_or(0, |metadata| metadata.len())
+		}),
+		SortOrder::Extension => {
+			paths.sort_by(|a, b| a.extension().cmp(&b.extension()));
 		}
 	}
+	// Files listed in the ordering go first, in order.
+	let ordering = options
+		.ordering
+		.iter()
+		.enumerate()
+		.map(|(idx, entry)| (entry.path.as_path(), idx))
+		.collect::<HashMap<_, _>>();
+	paths.sort_by_key(|path| {
+		let stripped_path = path.strip_prefix(root).unwrap_or(path);
+		ordering.get(stripped_path).copied().unwrap_or(usize::MAX)
+	});
+	Ok(paths)
+}
+
+/// Reads the symbolic link at `path`, which is at `stripped_path` in the
+/// archive, returning where it points to relative to the archive's root.
+fn tree_link(root: &Path, path: &Path, stripped_path: &Path) -> Result<PathBuf> {
+	let link = fs::read_link(path)?;
+	let outside_root = || Error::SymlinkOutsideRoot {
+		path: path.to_path_buf(),
+		link: link.clone(),
+	};
+	// Links in the header are relative to the root of the archive, rather
+	// than to the directory containing the link.
+	if link.is_absolute() {
+		return Ok(link
+			.strip_prefix(root)
+			.map_err(|_| outside_root())?
+			.to_path_buf());
+	}
+	let parent = stripped_path.parent().unwrap_or(Path::new(""));
+	let resolved = normalize_path(&parent.join(&link));
+	if resolved.starts_with("..") {
+		return Err(outside_root());
+	}
+	Ok(resolved)
+}
+
+/// Whether the file at `path`, which is at `stripped_path` in the archive, is
+/// packed as executable.
+fn is_tree_executable(glob: Option<&Glob>, path: &Path, stripped_path: &Path) -> bool {
+	glob.is_some_and(|glob| glob.is_match(stripped_path)) || is_executable::is_executable(path)
+}
+
+/// An integrity which takes up as much of the header as the real one of a file
+/// of `size` bytes, for working out the size of the header without hashing.
+fn placeholder_integrity(hasher: HashAlgorithm, size: usize) -> FileIntegrity {
+	let hash = vec![0; hasher.hash_len()];
+	let blocks = vec![hash.clone(); size.div_ceil(DEFAULT_BLOCK_SIZE)];
+	FileIntegrity::new(hasher, hash, DEFAULT_BLOCK_SIZE, blocks)
+}
+
+/// Where the data starts in an archive whose header JSON is `json_size`
+/// bytes.
+///
+/// The header is padded so that the data, which starts right after the 16
+/// bytes of framing and the padded JSON, is aligned.
+const fn data_start(json_size: usize, data_alignment: usize) -> usize {
+	(16 + json_size).next_multiple_of(data_alignment)
 }
 
 fn path_to_reverse_components(path: &Path) -> Result<VecDeque<String>> {
@@ -213,7 +1792,11 @@ fn path_to_reverse_components(path: &Path) -> Result<VecDeque<String>> {
 		.collect())
 }
 
-fn recursive_add_to_header(mut path: VecDeque<String>, file_or_symlink: Header, header: &mut Header) {
+fn recursive_add_to_header(
+	mut path: VecDeque<String>,
+	file_or_symlink: Header,
+	header: &mut Header,
+) {
 	let header_map = match header {
 		Header::Directory { files } => files,
 		_ => return,
@@ -234,12 +1817,22 @@ fn recursive_add_to_header(mut path: VecDeque<String>, file_or_symlink: Header,
 
 #[cfg(test)]
 mod test {
-	use super::AsarWriter;
+	use super::{AsarWriter, ExtensionStats, PackOptions, SortOrder};
 	use crate::{
+		error::Error,
 		header::{Header, TEST_ASAR},
-		reader::AsarReader,
+		ordering::parse_ordering_str,
+		reader::{AsarReader, EntryKind},
+		test_util::temp_path,
+	};
+	use include_dir::{include_dir, Dir};
+	use proptest::{collection::btree_map, prelude::*};
+	use std::{
+		collections::BTreeMap,
+		fs,
+		io::Cursor,
+		path::{Path, PathBuf},
 	};
-	use std::io::Cursor;
 
 	#[test]
 	pub fn round_trip() {
@@ -291,4 +1884,944 @@ mod test {
 			panic!("ASAR archives differ!");
 		}
 	}
+
+	#[test]
+	pub fn add_from_reader_with_progress() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let mut calls = Vec::new();
+		let mut writer = AsarWriter::new();
+		writer
+			.add_from_reader_with_progress(&reader, |done, total| calls.push((done, total)))
+			.expect("failed to add files from reader");
+		let total = reader.files().len();
+		assert_eq!(
+			calls,
+			(1..=total).map(|done| (done, total)).collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	pub fn try_from_reader() {
+		let mut writer = AsarWriter::new();
+		writer
+			.write_file("run.sh", b"#!/bin/sh", true)
+			.expect("failed to write file");
+		writer
+			.write_symlink("link", "run.sh")
+			.expect("failed to write symlink");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+
+		let mut repacked = Vec::new();
+		AsarWriter::try_from(&reader)
+			.expect("failed to convert reader")
+			.finalize(&mut repacked)
+			.expect("failed to finalize asar");
+		assert_eq!(repacked, out);
+		let repacked = AsarReader::new(&repacked, None).expect("failed to read asar");
+		assert!(repacked.files()[Path::new("run.sh")].executable());
+	}
+
+	#[test]
+	pub fn write_file_with_integrity() {
+		let reader = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let integrity = reader.files()[Path::new("file 1.txt")]
+			.integrity()
+			.expect("file should have integrity")
+			.clone();
+		let mut writer = AsarWriter::new();
+		writer
+			.write_file_with_integrity("patched.txt", b"patched", false, integrity.clone())
+			.expect("failed to write file");
+		assert_eq!(
+			writer.write_file_with_integrity(
+				"too long.txt",
+				vec![0; super::DEFAULT_BLOCK_SIZE + 1],
+				false,
+				integrity.clone()
+			),
+			Err(Error::InvalidIntegrity {
+				path: "too long.txt".into(),
+				reason: format!(
+					"expected 2 blocks of {} bytes, found 1",
+					super::DEFAULT_BLOCK_SIZE
+				),
+			})
+		);
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let (header, _) = Header::read(&mut &out[..]).expect("failed to read asar header");
+		let Header::Directory { files } = header else {
+			panic!("root should be a directory");
+		};
+		let Some(Header::File(file)) = files.get("patched.txt") else {
+			panic!("patched.txt should be a file");
+		};
+		assert_eq!(file.integrity(), Some(&integrity));
+	}
+
+	#[test]
+	pub fn into_bytes() {
+		let mut writer = AsarWriter::new();
+		writer
+			.write_file("advice.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		let bytes = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+		assert_eq!(
+			reader.files()[Path::new("advice.txt")].data(),
+			b"Don't Panic."
+		);
+	}
+
+	#[test]
+	#[cfg(unix)]
+	pub fn non_utf8_paths() {
+		use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+		let path = Path::new(OsStr::from_bytes(b"invalid \xff.txt"));
+		let mut writer = AsarWriter::new();
+		assert_eq!(
+			writer.write_file(path, b"Don't Panic.", false),
+			Err(Error::NonUtf8Path(path.to_path_buf()))
+		);
+		assert_eq!(
+			writer.write_symlink("link", path),
+			Err(Error::NonUtf8Path(path.to_path_buf()))
+		);
+
+		let mut writer = AsarWriter::builder().lossy_paths(true).build();
+		writer
+			.write_file(path, b"Don't Panic.", false)
+			.expect("failed to write file");
+		let bytes = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+		assert!(reader
+			.files()
+			.contains_key(Path::new("invalid \u{fffd}.txt")));
+	}
+
+	#[test]
+	pub fn finalize_to_path() {
+		let path = temp_path("finalize-to-path.asar");
+		std::fs::write(&path, b"not an asar").expect("failed to write file");
+		let mut writer = AsarWriter::new();
+		writer
+			.write_file("advice.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		let written = writer
+			.finalize_to_path(&path)
+			.expect("failed to finalize asar");
+		let bytes = std::fs::read(&path).expect("failed to read asar");
+		std::fs::remove_file(&path).expect("failed to clean up");
+		assert_eq!(written, bytes.len());
+		assert!(!path.with_extension("asar.tmp").exists());
+		let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+		assert_eq!(
+			reader.files()[Path::new("advice.txt")].data(),
+			b"Don't Panic."
+		);
+	}
+
+	#[test]
+	pub fn write_json() {
+		let mut writer = AsarWriter::new();
+		writer
+			.write_json("package.json", &serde_json::json!({ "name": "app" }))
+			.expect("failed to write json");
+		writer
+			.write_json("numbers.json", &[4, 8, 15, 16, 23, 42])
+			.expect("failed to write json");
+		let bytes = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+		assert_eq!(
+			reader.files()[Path::new("package.json")].data(),
+			br#"{"name":"app"}"#
+		);
+		assert_eq!(
+			reader.files()[Path::new("numbers.json")].data(),
+			b"[4,8,15,16,23,42]"
+		);
+	}
+
+	#[test]
+	pub fn single() {
+		let archive =
+			AsarWriter::single("bin/run.sh", b"#!/bin/sh", true).expect("failed to write archive");
+		let reader = AsarReader::new(&archive, None).expect("failed to read asar");
+		assert_eq!(reader.files().len(), 1);
+		let file = &reader.files()[Path::new("bin/run.sh")];
+		assert_eq!(file.data(), b"#!/bin/sh");
+		assert!(file.executable());
+	}
+
+	#[test]
+	pub fn clear() {
+		let mut writer = AsarWriter::builder().data_alignment(8).build();
+		writer
+			.write_file("draft.txt", b"Mostly harmless.", false)
+			.expect("failed to write file");
+		writer
+			.write_symlink("link.txt", "draft.txt")
+			.expect("failed to write symlink");
+		let capacity = writer.buffer.capacity();
+		writer.clear();
+		assert!(writer.buffer.is_empty());
+		assert_eq!(writer.buffer.capacity(), capacity);
+		writer
+			.write_file("final.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+
+		let mut fresh = AsarWriter::builder().data_alignment(8).build();
+		fresh
+			.write_file("final.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		assert_eq!(
+			writer.into_bytes().expect("failed to finalize asar"),
+			fresh.into_bytes().expect("failed to finalize asar")
+		);
+	}
+
+	#[test]
+	pub fn write_file_deferred() {
+		let files = [
+			("advice.txt", &b"Don't Panic."[..]),
+			("dir/answer.txt", b"42"),
+			("empty.txt", b""),
+		];
+		let mut eager = AsarWriter::new();
+		for (path, contents) in files {
+			eager
+				.write_file(path, contents, false)
+				.expect("failed to write file");
+		}
+		let expected = eager.into_bytes().expect("failed to finalize asar");
+		for parallel in [false, true] {
+			let mut deferred = AsarWriter::new();
+			for (path, contents) in files {
+				deferred
+					.write_file_deferred(path, contents, false)
+					.expect("failed to write file");
+			}
+			assert!(deferred
+				.files
+				.values()
+				.all(|file| file.integrity().is_none()));
+			let mut out = Vec::new();
+			if parallel {
+				deferred.finalize_parallel(&mut out)
+			} else {
+				deferred.finalize(&mut out)
+			}
+			.expect("failed to finalize asar");
+			let reader = AsarReader::new(&out, None).expect("failed to read asar");
+			for (path, result) in reader.verify_iter() {
+				result.unwrap_or_else(|err| panic!("{} should be valid: {err}", path.display()));
+			}
+			assert_eq!(
+				Header::read(&mut &out[..]).unwrap().0,
+				Header::read(&mut &expected[..]).unwrap().0
+			);
+		}
+	}
+
+	#[test]
+	pub fn path_conflict() {
+		let mut writer = AsarWriter::new();
+		writer
+			.write_file("advice.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		writer
+			.write_symlink("guide.txt", "advice.txt")
+			.expect("failed to write symlink");
+		assert_eq!(
+			writer.write_symlink("advice.txt", "guide.txt"),
+			Err(Error::PathConflict {
+				path: "advice.txt".into(),
+				existing_kind: EntryKind::File,
+				new_kind: EntryKind::Symlink,
+			})
+		);
+		assert_eq!(
+			writer.write_file("guide.txt", b"Mostly harmless.", false),
+			Err(Error::PathConflict {
+				path: "guide.txt".into(),
+				existing_kind: EntryKind::Symlink,
+				new_kind: EntryKind::File,
+			})
+		);
+		assert_eq!(
+			writer.write_file("advice.txt", b"Mostly harmless.", false),
+			Err(Error::FileAlreadyWritten("advice.txt".into()))
+		);
+
+		let mut other = AsarWriter::new();
+		other
+			.write_symlink("advice.txt", "guide.txt")
+			.expect("failed to write symlink");
+		assert_eq!(
+			writer.merge(other),
+			Err(Error::PathConflict {
+				path: "advice.txt".into(),
+				existing_kind: EntryKind::File,
+				new_kind: EntryKind::Symlink,
+			})
+		);
+	}
+
+	#[test]
+	pub fn stats() {
+		let mut writer = AsarWriter::new();
+		for (path, contents) in [
+			("index.js", &b"Don't Panic."[..]),
+			("lib/util.js", b"Mostly harmless."),
+			("native.node", b"42"),
+			("LICENSE", b"MIT"),
+		] {
+			writer
+				.write_file(path, contents, false)
+				.expect("failed to write file");
+		}
+		writer
+			.write_symlink("link.js", "index.js")
+			.expect("failed to write symlink");
+		let stats = writer.stats();
+		assert_eq!(stats.files, 4);
+		assert_eq!(stats.bytes, 33);
+		assert_eq!(stats.symlinks, 1);
+		assert_eq!(stats.by_extension["js"], ExtensionStats {
+			files: 2,
+			bytes: 28,
+			largest: 16,
+		});
+		assert_eq!(stats.by_extension["node"].files, 1);
+		assert_eq!(stats.by_extension[""].bytes, 3);
+	}
+
+	#[test]
+	pub fn from_iter() {
+		let files = BTreeMap::from([
+			(PathBuf::from("a.txt"), b"Don't Panic.".to_vec()),
+			(PathBuf::from("b/b.txt"), b"Mostly harmless.".to_vec()),
+		]);
+		let mut writer: AsarWriter = files.clone().into_iter().collect();
+		writer.extend([(PathBuf::from("c.txt"), Vec::new())]);
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.files().len(), 3);
+		for (path, data) in &files {
+			let file = &reader.files()[path];
+			assert_eq!(file.data(), data.as_slice());
+			assert!(!file.executable());
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "has already been written")]
+	pub fn from_iter_duplicate() {
+		let _: AsarWriter = [
+			(PathBuf::from("a.txt"), b"Don't Panic.".to_vec()),
+			(PathBuf::from("a.txt"), b"Mostly harmless.".to_vec()),
+		]
+		.into_iter()
+		.collect();
+	}
+
+	#[test]
+	pub fn from_existing() {
+		let (header, offset) = Header::read(&mut &*TEST_ASAR).expect("failed to read header");
+		let adopt = || {
+			AsarWriter::from_existing(header.clone(), TEST_ASAR.to_vec(), offset)
+				.expect("failed to adopt archive")
+		};
+		let mut out = Vec::new();
+		adopt().finalize(&mut out).expect("failed to finalize asar");
+		let original = AsarReader::new(TEST_ASAR, None).expect("failed to read asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.files(), original.files());
+		assert_eq!(reader.symlinks(), original.symlinks());
+
+		let mut writer = adopt();
+		writer
+			.update_file(
+				"folder 1/file 3.txt",
+				b"So long, and thanks for all the fish.",
+				true,
+			)
+			.expect("failed to update file");
+		assert_eq!(
+			writer.update_file("file 3.txt", b"", false),
+			Err(Error::FileNotFound("file 3.txt".into()))
+		);
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		let updated = &reader.files()[Path::new("folder 1/file 3.txt")];
+		assert_eq!(updated.data(), b"So long, and thanks for all the fish.");
+		assert!(updated.executable());
+		for (path, file) in original.files() {
+			if path != Path::new("folder 1/file 3.txt") {
+				assert_eq!(&reader.files()[path], file);
+			}
+		}
+
+		assert_eq!(
+			AsarWriter::from_existing(header, TEST_ASAR[..offset + 1].to_vec(), offset).err(),
+			Some(Error::Truncated)
+		);
+	}
+
+	#[test]
+	pub fn merge() {
+		let mut a = AsarWriter::new();
+		a.write_file("a.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		a.write_symlink("link", "b/b.txt")
+			.expect("failed to write symlink");
+		let mut b = AsarWriter::new();
+		b.write_file("b/b.txt", b"Mostly harmless.", true)
+			.expect("failed to write file");
+		b.write_file("c.txt", b"", false)
+			.expect("failed to write file");
+		a.merge(b).expect("failed to merge");
+
+		let mut conflict = AsarWriter::new();
+		conflict
+			.write_file("a.txt", b"So long, and thanks for all the fish.", false)
+			.expect("failed to write file");
+		assert_eq!(
+			a.merge(conflict),
+			Err(crate::error::Error::FileAlreadyWritten("a.txt".into()))
+		);
+
+		let mut out = Vec::new();
+		a.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.files().len(), 3);
+		assert_eq!(reader.files()[Path::new("a.txt")].data(), b"Don't Panic.");
+		assert_eq!(
+			reader.files()[Path::new("b/b.txt")].data(),
+			b"Mostly harmless."
+		);
+		assert_eq!(
+			reader.read(Path::new("link")).unwrap().data(),
+			b"Mostly harmless."
+		);
+	}
+
+	static ASAR_CONTENTS: Dir = include_dir!("$CARGO_MANIFEST_DIR/data/contents");
+
+	#[test]
+	pub fn write_tree() {
+		let mut writer = AsarWriter::new();
+		writer
+			.write_tree(
+				concat!(env!("CARGO_MANIFEST_DIR"), "/data/contents"),
+				PackOptions::default(),
+			)
+			.expect("failed to pack directory");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.files().len(), 6);
+		assert_eq!(reader.symlinks().len(), 2);
+		for (path, file) in reader.files() {
+			let real_file = ASAR_CONTENTS
+				.get_file(path)
+				.unwrap_or_else(|| panic!("packed invalid file {}", path.display()));
+			assert_eq!(real_file.contents(), file.data());
+		}
+	}
+
+	/// Packs the tree that `data/test.asar` was made from with
+	/// `@electron/asar`, and checks that the output matches it byte for byte.
+	#[test]
+	pub fn electron_compatible_framing() {
+		let pack = || {
+			let mut writer = AsarWriter::new();
+			writer
+				.write_tree(
+					concat!(env!("CARGO_MANIFEST_DIR"), "/data/contents"),
+					PackOptions::default(),
+				)
+				.expect("failed to pack directory");
+			writer.into_bytes().expect("failed to finalize asar")
+		};
+		let out = pack();
+		assert_eq!(out, TEST_ASAR);
+		assert_eq!(
+			pack(),
+			out,
+			"packing the same tree should give the same archive"
+		);
+	}
+
+	#[test]
+	pub fn framing_layout() {
+		// Names of different lengths give JSON of every length modulo 4.
+		for name in ["a", "ab", "abc", "abcd"] {
+			let mut writer = AsarWriter::new();
+			writer
+				.write_file(name, b"42", false)
+				.expect("failed to write file");
+			let out = writer.into_bytes().expect("failed to finalize asar");
+			let field = |idx: usize| {
+				u32::from_le_bytes(out[idx * 4..idx * 4 + 4].try_into().unwrap()) as usize
+			};
+			let padded_json_size = field(3).next_multiple_of(4);
+			assert_eq!(field(0), 4);
+			assert_eq!(field(1), padded_json_size + 8);
+			assert_eq!(field(2), padded_json_size + 4);
+			assert!(out[16 + field(3)..16 + padded_json_size]
+				.iter()
+				.all(|&byte| byte == 0));
+			assert_eq!(&out[16 + padded_json_size..], b"42");
+			let (_, offset) = Header::read(&mut &*out).expect("failed to read asar header");
+			assert_eq!(offset, 16 + padded_json_size);
+		}
+	}
+
+	#[test]
+	pub fn write_tree_exclude_hidden() {
+		let root = temp_path("exclude-hidden");
+		fs::create_dir_all(root.join(".hidden/nested")).expect("failed to create directory");
+		fs::create_dir_all(root.join("visible")).expect("failed to create directory");
+		for path in [
+			".hidden/config",
+			".hidden/nested/file.txt",
+			"visible/.env",
+			"visible/file.txt",
+		] {
+			fs::write(root.join(path), path).expect("failed to write file");
+		}
+
+		let mut writer = AsarWriter::new();
+		writer
+			.write_tree(&root, PackOptions {
+				exclude_hidden: true,
+				..PackOptions::default()
+			})
+			.expect("failed to pack directory");
+		fs::remove_dir_all(&root).expect("failed to clean up");
+		let bytes = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+		assert_eq!(reader.files().keys().collect::<Vec<_>>(), [Path::new(
+			"visible/file.txt"
+		)]);
+	}
+
+	#[test]
+	#[cfg(unix)]
+	pub fn write_tree_follow_symlinks() {
+		let contents = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/data/contents"));
+		let root = temp_path("follow-symlinks");
+		fs::create_dir_all(root.join("dir")).expect("failed to create directory");
+		std::os::unix::fs::symlink(contents, root.join("root link"))
+			.expect("failed to create symlink");
+		std::os::unix::fs::symlink(contents.join("folder 1"), root.join("dir/dir link"))
+			.expect("failed to create symlink");
+
+		let pack = |root: &Path, follow_symlinks: bool| {
+			let mut writer = AsarWriter::new();
+			writer
+				.write_tree(root, PackOptions {
+					follow_symlinks,
+					..PackOptions::default()
+				})
+				.expect("failed to pack directory");
+			let mut out = Vec::new();
+			writer.finalize(&mut out).expect("failed to finalize asar");
+			out
+		};
+		let out = pack(&root.join("root link"), false);
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.files().len(), 6);
+		assert_eq!(reader.symlinks().len(), 2);
+
+		let out = pack(&root.join("dir"), false);
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert!(reader.files().is_empty());
+
+		let out = pack(&root.join("dir"), true);
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.files().len(), 4);
+		assert_eq!(
+			reader.files()[Path::new("dir link/file 3.txt")].data(),
+			ASAR_CONTENTS
+				.get_file("folder 1/file 3.txt")
+				.unwrap()
+				.contents()
+		);
+		fs::remove_dir_all(&root).expect("failed to clean up");
+	}
+
+	#[test]
+	#[cfg(unix)]
+	pub fn write_tree_relative_symlinks() {
+		let temp = temp_path("relative-symlinks");
+		let root = temp.join("root");
+		fs::create_dir_all(root.join("dir")).expect("failed to create directory");
+		fs::write(root.join("dir/a.txt"), b"a").expect("failed to write file");
+		fs::write(root.join("b.txt"), b"b").expect("failed to write file");
+		fs::write(temp.join("outside.txt"), b"c").expect("failed to write file");
+		std::os::unix::fs::symlink("a.txt", root.join("dir/sibling"))
+			.expect("failed to create symlink");
+		std::os::unix::fs::symlink("./../b.txt", root.join("dir/parent"))
+			.expect("failed to create symlink");
+
+		let mut writer = AsarWriter::new();
+		writer
+			.write_tree(&root, PackOptions::default())
+			.expect("failed to pack directory");
+		let out = writer.into_bytes().expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(
+			reader.symlinks()[Path::new("dir/sibling")],
+			Path::new("dir/a.txt")
+		);
+		assert_eq!(
+			reader.symlinks()[Path::new("dir/parent")],
+			Path::new("b.txt")
+		);
+
+		std::os::unix::fs::symlink("../../outside.txt", root.join("dir/escape"))
+			.expect("failed to create symlink");
+		assert!(matches!(
+			AsarWriter::new().write_tree(&root, PackOptions::default()),
+			Err(Error::SymlinkOutsideRoot { .. })
+		));
+		fs::remove_dir_all(&temp).expect("failed to clean up");
+	}
+
+	#[test]
+	pub fn write_tree_jobs() {
+		let pack = |jobs| {
+			let mut writer = AsarWriter::new();
+			writer
+				.write_tree(
+					concat!(env!("CARGO_MANIFEST_DIR"), "/data/contents"),
+					PackOptions {
+						ordering: parse_ordering_str("folder 1/folder 3/file 6.txt"),
+						jobs,
+						..PackOptions::default()
+					},
+				)
+				.expect("failed to pack directory");
+			writer
+		};
+		let sequential = pack(None).into_bytes().expect("failed to finalize asar");
+		for jobs in [0, 1, 4] {
+			let parallel = pack(Some(jobs))
+				.into_bytes()
+				.expect("failed to finalize asar");
+			assert_eq!(
+				parallel, sequential,
+				"{jobs} jobs should give the same archive"
+			);
+		}
+	}
+
+	#[test]
+	pub fn write_tree_ordering() {
+		let mut writer = AsarWriter::new();
+		writer
+			.write_tree(
+				concat!(env!("CARGO_MANIFEST_DIR"), "/data/contents"),
+				PackOptions {
+					ordering: parse_ordering_str("folder 1/folder 3/file 6.txt\nfile 2.txt"),
+					..PackOptions::default()
+				},
+			)
+			.expect("failed to pack directory");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		let manifest = reader.manifest();
+		let offset_of = |path: &str| {
+			manifest
+				.files
+				.iter()
+				.find(|file| file.path == Path::new(path))
+				.and_then(|file| file.offset)
+				.unwrap()
+		};
+		assert_eq!(offset_of("folder 1/folder 3/file 6.txt"), 0);
+		assert_eq!(offset_of("file 2.txt"), 31);
+	}
+
+	#[test]
+	pub fn plan_tree() {
+		let root = temp_path("plan-tree");
+		fs::create_dir_all(root.join("dir")).expect("failed to create directory");
+		fs::write(root.join("small.txt"), b"Don't Panic.").expect("failed to write file");
+		fs::write(root.join("empty.txt"), b"").expect("failed to write file");
+		fs::write(root.join(".hidden"), b"hidden").expect("failed to write file");
+		// Large enough to have more than one block hash.
+		fs::write(root.join("dir/large.bin"), vec![42; 5 * 1024 * 1024])
+			.expect("failed to write file");
+		fs::write(root.join("dir/unpacked.node"), b"native").expect("failed to write file");
+
+		for options in [PackOptions::default(), PackOptions {
+			unpack: Some("**/*.node".to_owned()),
+			executable: Some("**/*.bin".to_owned()),
+			exclude_hidden: true,
+			sort: SortOrder::Size,
+			record_mtimes: true,
+			..PackOptions::default()
+		}] {
+			let writer = AsarWriter::builder().data_alignment(4096).build();
+			let plan = writer
+				.plan_tree(&root, &options)
+				.expect("failed to plan directory");
+			let mut writer = AsarWriter::builder().data_alignment(4096).build();
+			writer
+				.write_tree(&root, options)
+				.expect("failed to pack directory");
+			let bytes = writer.into_bytes().expect("failed to finalize asar");
+			let reader = AsarReader::new(&bytes, None).expect("failed to read asar");
+			assert_eq!(plan.size, bytes.len());
+			let mut files = reader
+				.files()
+				.iter()
+				.map(|(path, file)| (path.clone(), file.data().len()))
+				.collect::<Vec<_>>();
+			files.sort_by_key(|(path, _)| match reader.header().find(path) {
+				Some(Header::File(file)) => file.offset(),
+				_ => None,
+			});
+			assert_eq!(plan.files, files);
+			assert!(plan.symlinks.is_empty());
+		}
+		fs::remove_dir_all(&root).expect("failed to clean up");
+
+		let contents = concat!(env!("CARGO_MANIFEST_DIR"), "/data/contents");
+		let plan = AsarWriter::new()
+			.plan_tree(contents, &PackOptions::default())
+			.expect("failed to plan directory");
+		assert_eq!(plan.size, TEST_ASAR.len());
+		assert_eq!(plan.symlinks, [
+			(
+				PathBuf::from("symlink 1"),
+				PathBuf::from("folder 1/file 3.txt")
+			),
+			(
+				PathBuf::from("symlink 2"),
+				PathBuf::from("folder 1/folder 3/file 6.txt")
+			),
+		]);
+	}
+
+	#[test]
+	pub fn plan() {
+		let mut writer = AsarWriter::new();
+		writer
+			.write_file("advice.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		writer
+			.write_file_deferred("dir/answer.txt", b"42", true)
+			.expect("failed to write file");
+		writer
+			.write_symlink("guide.txt", "advice.txt")
+			.expect("failed to write symlink");
+		let plan = writer.plan().expect("failed to plan asar");
+		assert_eq!(plan.files, [
+			(PathBuf::from("advice.txt"), 12),
+			(PathBuf::from("dir/answer.txt"), 2)
+		]);
+		assert_eq!(plan.symlinks, [(
+			PathBuf::from("guide.txt"),
+			PathBuf::from("advice.txt")
+		)]);
+		assert_eq!(
+			plan.size,
+			writer.into_bytes().expect("failed to finalize asar").len()
+		);
+	}
+
+	#[test]
+	pub fn write_tree_sort() {
+		let mut writer = AsarWriter::new();
+		writer
+			.write_tree(
+				concat!(env!("CARGO_MANIFEST_DIR"), "/data/contents"),
+				PackOptions {
+					ordering: parse_ordering_str("folder 1/folder 3/file 6.txt"),
+					sort: SortOrder::Size,
+					..PackOptions::default()
+				},
+			)
+			.expect("failed to pack directory");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		let files = reader
+			.files_by_offset()
+			.into_iter()
+			.map(|(path, file)| (file.offset().unwrap(), path))
+			.collect::<Vec<_>>();
+		assert_eq!(files, [
+			(0, Path::new("folder 1/folder 3/file 6.txt")),
+			(31, Path::new("folder 1/file 3.txt")),
+			(34, Path::new("file 1.txt")),
+			(47, Path::new("file 2.txt")),
+			(60, Path::new("folder 1/folder 2/file 4.txt")),
+			(80, Path::new("folder 1/folder 2/file 5.txt")),
+		]);
+	}
+
+	#[test]
+	pub fn write_tree_executable() {
+		let mut writer = AsarWriter::new();
+		writer
+			.write_tree(
+				concat!(env!("CARGO_MANIFEST_DIR"), "/data/contents"),
+				PackOptions {
+					executable: Some("folder 1/**/*.txt".into()),
+					..PackOptions::default()
+				},
+			)
+			.expect("failed to pack directory");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let manifest = AsarReader::new(&out, None)
+			.expect("failed to read asar")
+			.manifest();
+		for file in manifest.files {
+			assert_eq!(
+				file.executable,
+				file.path.starts_with("folder 1"),
+				"{}",
+				file.path.display()
+			);
+		}
+	}
+
+	fn file_tree() -> impl Strategy<Value = BTreeMap<PathBuf, Vec<u8>>> {
+		let path = prop::collection::vec("[a-zA-Z0-9 _-]{1,9}", 1..4)
+			.prop_map(|components| components.iter().collect::<PathBuf>());
+		btree_map(path, prop::collection::vec(any::<u8>(), 0..64), 0..16).prop_filter(
+			"a file cannot also be a directory",
+			|files| {
+				files
+					.keys()
+					.all(|a| !files.keys().any(|b| a != b && b.starts_with(a)))
+			},
+		)
+	}
+
+	#[test]
+	pub fn framing_alignment() {
+		// Cover every possible `json_size % 4`, so the alignment padding is
+		// exercised for all its possible lengths.
+		let mut paddings = [false; 4];
+		for name_len in 1..=8 {
+			let mut writer = AsarWriter::new();
+			writer
+				.write_file("a".repeat(name_len), b"Don't Panic.", false)
+				.expect("failed to write file");
+			let mut out = Vec::new();
+			writer.finalize(&mut out).expect("failed to finalize asar");
+			let json_size = u32::from_le_bytes(out[12..16].try_into().unwrap()) as usize;
+			let (_, offset) = Header::read(&mut &out[..]).expect("failed to read asar header");
+			paddings[json_size % 4] = true;
+			assert_eq!(offset % 4, 0);
+			assert_eq!(offset, 16 + json_size + (4 - json_size % 4) % 4);
+			assert_eq!(&out[offset..], b"Don't Panic.");
+		}
+		assert_eq!(paddings, [true; 4]);
+	}
+
+	#[test]
+	pub fn data_alignment() {
+		for alignment in [1, 4, 8, 64, 4096] {
+			let mut writer = AsarWriter::builder().data_alignment(alignment).build();
+			writer
+				.write_file("advice.txt", b"Don't Panic.", false)
+				.expect("failed to write file");
+			let mut out = Vec::new();
+			writer.finalize(&mut out).expect("failed to finalize asar");
+			let (_, offset) = Header::read(&mut &out[..]).expect("failed to read asar header");
+			assert_eq!(offset % alignment, 0);
+			assert_eq!(&out[offset..], b"Don't Panic.");
+			let reader = AsarReader::new(&out, None).expect("failed to read asar");
+			assert_eq!(
+				reader.files()[Path::new("advice.txt")].data(),
+				b"Don't Panic."
+			);
+		}
+	}
+
+	#[test]
+	pub fn pretty_header() {
+		let mut writer = AsarWriter::builder().pretty_header(true).build();
+		writer
+			.write_file("advice.txt", b"Don't Panic.", false)
+			.expect("failed to write file");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		let json_size = u32::from_le_bytes(out[12..16].try_into().unwrap()) as usize;
+		let json = std::str::from_utf8(&out[16..16 + json_size]).expect("header should be UTF-8");
+		assert!(json.contains('\n'));
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(
+			reader.files()[Path::new("advice.txt")].data(),
+			b"Don't Panic."
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "compression")]
+	pub fn compression() {
+		use crate::header::{Compression, CompressionMethod};
+
+		let data = b"Don't Panic. ".repeat(1000);
+		let mut writer = AsarWriter::builder()
+			.compress(CompressionMethod::Gzip)
+			.build();
+		writer
+			.write_file("advice.txt", &data, false)
+			.expect("failed to write file");
+		let mut out = Vec::new();
+		writer.finalize(&mut out).expect("failed to finalize asar");
+		assert!(out.len() < data.len());
+		let (header, _) = Header::read(&mut &out[..]).expect("failed to read header");
+		let Header::Directory { files } = header else {
+			panic!("root should be a directory");
+		};
+		let Some(Header::File(file)) = files.get("advice.txt") else {
+			panic!("advice.txt should be a file");
+		};
+		assert_eq!(
+			file.compression(),
+			Some(&Compression {
+				method: CompressionMethod::Gzip,
+				original_size: data.len(),
+			})
+		);
+		assert!(file.size() < data.len());
+		let reader = AsarReader::new(&out, None).expect("failed to read asar");
+		assert_eq!(reader.files()[Path::new("advice.txt")].data(), data);
+	}
+
+	proptest! {
+		#[test]
+		fn round_trip_random_trees(files in file_tree()) {
+			let mut writer = AsarWriter::new();
+			for (path, data) in &files {
+				writer.write_file(path, data, false).expect("failed to write file");
+			}
+			let mut out = Vec::new();
+			let written = writer.finalize(&mut out).expect("failed to finalize asar");
+			prop_assert_eq!(written, out.len());
+			let reader = AsarReader::new(&out, None).expect("failed to read asar");
+			prop_assert_eq!(reader.files().len(), files.len());
+			for (path, data) in &files {
+				let file = reader.files().get(path);
+				prop_assert!(file.is_some(), "missing file {}", path.display());
+				prop_assert_eq!(file.unwrap().data(), data.as_slice());
+			}
+		}
+	}
 }